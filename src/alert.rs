@@ -0,0 +1,216 @@
+use crate::config;
+use crate::exercise::Exercise;
+use crate::progress::Outcome;
+use std::io::{self, Write};
+use std::process::{Command, Stdio};
+
+// What a check in watch mode amounted to, worth telling a user whose
+// terminal is in the background
+#[derive(Debug)]
+pub enum Event<'a> {
+    // The checked exercise passed, and nothing after it is left to do
+    Passed(&'a Exercise),
+    Failed(&'a Exercise),
+    // `from` passed and watch mode moved on to the unfinished `to`
+    Next {
+        from: &'a Exercise,
+        to: &'a Exercise,
+    },
+}
+
+impl Event<'_> {
+    // The name used by the `notify.<name>` setting and `KIGA_EVENT`
+    fn name(&self) -> &'static str {
+        match self {
+            Event::Passed(_) => "passed",
+            Event::Failed(_) => "failed",
+            Event::Next { .. } => "next",
+        }
+    }
+
+    // The exercise the user should look at now
+    fn exercise(&self) -> &Exercise {
+        match self {
+            Event::Passed(exercise) | Event::Failed(exercise) => exercise,
+            Event::Next { to, .. } => to,
+        }
+    }
+
+    fn message(&self) -> String {
+        match self {
+            Event::Passed(exercise) => format!("{} passed", exercise.name),
+            Event::Failed(exercise) => format!("{} isn't done yet", exercise.name),
+            Event::Next { from, to } => format!("{} passed, moved on to {}", from.name, to.name),
+        }
+    }
+}
+
+// Events are about the same exercises when they point at the same entries
+impl PartialEq for Event<'_> {
+    fn eq(&self, other: &Self) -> bool {
+        use std::ptr::eq;
+        match (self, other) {
+            (Event::Passed(a), Event::Passed(b)) | (Event::Failed(a), Event::Failed(b)) => {
+                eq(*a, *b)
+            }
+            (Event::Next { from: a, to: b }, Event::Next { from: c, to: d }) => {
+                eq(*a, *c) && eq(*b, *d)
+            }
+            _ => false,
+        }
+    }
+}
+
+// What a pass over the `checked` exercises, in the order they were checked,
+// amounts to. Passes that move on to the next unfinished exercise after the
+// first one stop there, the others check every exercise
+pub fn event<'a>(checked: &[(&'a Exercise, Outcome)], moves_on: bool) -> Option<Event<'a>> {
+    let (first, outcome) = *checked.first()?;
+    let unfinished = checked.iter().find(|(_, o)| *o != Outcome::Passed);
+    Some(match unfinished {
+        None => Event::Passed(first),
+        Some((to, _)) if moves_on && outcome == Outcome::Passed => Event::Next { from: first, to },
+        Some((exercise, _)) => Event::Failed(exercise),
+    })
+}
+
+// A way to get the user's attention
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Method {
+    // The terminal bell
+    Bell,
+    // A desktop notification through the OSC 9 escape, as in iTerm2 and Windows Terminal
+    Osc9,
+    // A desktop notification through the OSC 777 escape, as in VTE terminals and foot
+    Osc777,
+    // The `notify.command` setting, like `notify-send`
+    Command,
+}
+
+const EVENTS: [&str; 3] = ["passed", "failed", "next"];
+
+// Parse a comma separated list of methods, like `bell, osc9`
+fn methods(spec: &str) -> Result<Vec<Method>, String> {
+    spec.split(',')
+        .map(str::trim)
+        .filter(|name| !name.is_empty())
+        .map(|name| match name {
+            "bell" => Ok(Method::Bell),
+            "osc9" => Ok(Method::Osc9),
+            "osc777" => Ok(Method::Osc777),
+            "command" => Ok(Method::Command),
+            _ => Err(format!(
+                "Unknown notification '{name}', expected bell, osc9, osc777 or command"
+            )),
+        })
+        .collect()
+}
+
+// Problems with the notification settings, reported when watch mode starts
+pub fn problems() -> Vec<String> {
+    let settings = config::get();
+    let mut problems = Vec::new();
+    for event in EVENTS {
+        let Some(spec) = settings.notify_on(event) else {
+            continue;
+        };
+        match methods(spec) {
+            Ok(methods) if methods.contains(&Method::Command) => {
+                if settings.notify_command().is_none() {
+                    problems.push(format!(
+                        "notify.{event} uses `command`, but notify.command isn't set"
+                    ));
+                }
+            }
+            Ok(_) => {}
+            Err(problem) => problems.push(format!("notify.{event}: {problem}")),
+        }
+    }
+    problems
+}
+
+// Signal `event` the ways the user asked for, if any
+pub fn notify(event: &Event) {
+    let settings = config::get();
+    let Some(methods) = settings
+        .notify_on(event.name())
+        .and_then(|spec| methods(spec).ok())
+    else {
+        return;
+    };
+    let message = event.message();
+    let mut out = io::stdout();
+    for method in methods {
+        let _ = match method {
+            Method::Bell => write!(out, "\x07"),
+            Method::Osc9 => write!(out, "\x1b]9;kiga: {message}\x07"),
+            Method::Osc777 => write!(out, "\x1b]777;notify;kiga;{message}\x07"),
+            Method::Command => {
+                if let Some(command) = settings.notify_command() {
+                    run_command(command, event, &message);
+                }
+                Ok(())
+            }
+        };
+    }
+    let _ = out.flush();
+}
+
+// Run the notification command with the message as its last argument
+fn run_command(command: &str, event: &Event, message: &str) {
+    let Ok(words) = shell_words::split(command) else {
+        return;
+    };
+    let Some((program, args)) = words.split_first() else {
+        return;
+    };
+    let exercise = event.exercise();
+    let _ = Command::new(program)
+        .args(args)
+        .arg(message)
+        .env("KIGA_EVENT", event.name())
+        .env("KIGA_EXERCISE", &exercise.name)
+        .env("KIGA_PATH", &exercise.path)
+        .env("KIGA_MESSAGE", message)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status();
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_event() {
+        let (a, b) = (Exercise::named("a"), Exercise::named("b"));
+        assert_eq!(event(&[], true), None);
+        assert_eq!(
+            event(&[(&a, Outcome::Passed), (&b, Outcome::Passed)], true),
+            Some(Event::Passed(&a))
+        );
+        assert_eq!(
+            event(&[(&a, Outcome::Failed)], true),
+            Some(Event::Failed(&a))
+        );
+        assert_eq!(
+            event(&[(&a, Outcome::Passed), (&b, Outcome::Pending)], true),
+            Some(Event::Next { from: &a, to: &b })
+        );
+        assert_eq!(
+            event(&[(&a, Outcome::Passed), (&b, Outcome::Pending)], false),
+            Some(Event::Failed(&b))
+        );
+    }
+
+    #[test]
+    fn test_methods() {
+        assert_eq!(
+            methods("bell, osc777,command"),
+            Ok(vec![Method::Bell, Method::Osc777, Method::Command])
+        );
+        assert_eq!(methods(""), Ok(Vec::new()));
+        assert!(methods("bell,popup").is_err());
+    }
+}
@@ -0,0 +1,732 @@
+use crate::criteria::Criterion;
+use crate::exercise::ExerciseList;
+use crate::graph::{self, GraphError};
+use crate::root::normalize;
+use console::style;
+use glob::glob;
+use regex::Regex;
+use serde::Deserialize;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fmt::{self, Display, Formatter};
+use std::fs;
+use std::ops::Range;
+use std::path::{Component, Path, PathBuf};
+use toml::Spanned;
+
+// The directory every exercise has to live in
+const EXERCISES_DIR: &str = "exercises";
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Severity {
+    // The catalogue can't be used until this is fixed
+    Error,
+    // The catalogue works, but something looks off
+    Warning,
+}
+
+// A problem found while loading the catalogue, optionally pointing
+// at the line and column of info.toml it comes from
+#[derive(Debug, PartialEq)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    pub file: PathBuf,
+    // 1-based line and column
+    pub position: Option<(usize, usize)>,
+    // The source line the position points into
+    pub snippet: Option<String>,
+    pub message: String,
+}
+
+impl Diagnostic {
+    fn new(severity: Severity, file: &Path, message: String) -> Diagnostic {
+        Diagnostic {
+            severity,
+            file: file.to_path_buf(),
+            position: None,
+            snippet: None,
+            message,
+        }
+    }
+
+    // Point the diagnostic at a byte offset of the source
+    fn at(mut self, source: &str, offset: usize) -> Diagnostic {
+        let offset = offset.min(source.len());
+        let line_start = source[..offset].rfind('\n').map_or(0, |i| i + 1);
+        let line = source[..offset].matches('\n').count() + 1;
+        let column = source[line_start..offset].chars().count() + 1;
+        self.position = Some((line, column));
+        self.snippet = source[line_start..].lines().next().map(str::to_string);
+        self
+    }
+
+    fn at_span(self, source: &str, span: Option<Range<usize>>) -> Diagnostic {
+        match span {
+            Some(span) => self.at(source, span.start),
+            None => self,
+        }
+    }
+
+    pub fn is_error(&self) -> bool {
+        self.severity == Severity::Error
+    }
+}
+
+impl Display for Diagnostic {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(f, "{}", self.file.display())?;
+        if let Some((line, column)) = self.position {
+            write!(f, ":{line}:{column}")?;
+        }
+        let severity = match self.severity {
+            Severity::Error => style("error").red().bold(),
+            Severity::Warning => style("warning").yellow().bold(),
+        };
+        write!(f, ": {severity}: {}", self.message)?;
+        if let (Some((line, column)), Some(snippet)) = (self.position, &self.snippet) {
+            let gutter = " ".repeat(line.to_string().len());
+            write!(f, "\n{gutter} {}", style("|").blue())?;
+            write!(
+                f,
+                "\n{} {} {snippet}",
+                style(line).blue().bold(),
+                style("|").blue()
+            )?;
+            write!(
+                f,
+                "\n{gutter} {} {}{}",
+                style("|").blue(),
+                " ".repeat(column - 1),
+                style("^").red().bold()
+            )?;
+        }
+        Ok(())
+    }
+}
+
+// The positions of the values that diagnostics point at. This is
+// parsed separately so `Exercise` can keep plain fields
+#[derive(Deserialize)]
+struct Spans {
+    #[serde(default)]
+    exercises: Vec<EntrySpans>,
+    #[serde(default)]
+    categories: BTreeMap<String, Spanned<Vec<String>>>,
+    namespace: Option<Spanned<String>>,
+    include: Option<Spanned<Vec<String>>>,
+    hooks: Option<Spanned<toml::Table>>,
+}
+
+impl Spans {
+    fn include(&self) -> Range<usize> {
+        self.include.as_ref().map(Spanned::span).unwrap_or_default()
+    }
+}
+
+#[derive(Deserialize)]
+struct EntrySpans {
+    name: Spanned<String>,
+    path: Spanned<PathBuf>,
+    hint: Option<Spanned<String>>,
+    hints: Option<Spanned<Vec<String>>>,
+    hint_file: Option<Spanned<PathBuf>>,
+    template: Option<Spanned<PathBuf>>,
+    solution: Option<Spanned<PathBuf>>,
+}
+
+// A single catalogue file, either info.toml or one of its includes
+struct Unit {
+    file: PathBuf,
+    source: String,
+    spans: Spans,
+    // The namespace of an included catalogue, None for info.toml itself
+    namespace: Option<String>,
+    // The unqualified names of the exercises defined in this file
+    local: HashSet<String>,
+}
+
+impl Unit {
+    fn diagnostic(&self, severity: Severity, message: String, span: Range<usize>) -> Diagnostic {
+        Diagnostic::new(severity, &self.file, message).at(&self.source, span.start)
+    }
+
+    // Qualify a name used in this file. Names defined in the same file get
+    // its namespace, other names are taken to be already qualified
+    fn qualify(&self, name: &str) -> String {
+        match &self.namespace {
+            Some(namespace) if self.local.contains(name) => format!("{namespace}/{name}"),
+            _ => name.to_string(),
+        }
+    }
+}
+
+// Load and validate the catalogue at `path`, with exercises in topological order.
+// Catalogues listed in its `include` globs are merged in, their exercises
+// named `<namespace>/<name>`. On success the warnings are returned alongside
+// the catalogue, otherwise every diagnostic found is returned
+pub fn load(path: &Path) -> Result<(ExerciseList, Vec<Diagnostic>), Vec<Diagnostic>> {
+    let (mut list, root) = read(path, None).map_err(|d| vec![d])?;
+    let mut diagnostics = Vec::new();
+    if let Some(namespace) = &root.spans.namespace {
+        diagnostics.push(root.diagnostic(
+            Severity::Error,
+            "`namespace` is only allowed in included catalogues".to_string(),
+            namespace.span(),
+        ));
+    }
+
+    // Where each merged exercise comes from, as (unit, entry) indices
+    let mut origins: Vec<(usize, usize)> = (0..list.exercises.len()).map(|i| (0, i)).collect();
+    let mut units = vec![root];
+    for pattern in list.include.clone() {
+        let include_span = units[0].spans.include();
+        let files = match glob(&pattern) {
+            Ok(paths) => {
+                let mut files: Vec<PathBuf> = paths.filter_map(Result::ok).collect();
+                files.sort();
+                files
+            }
+            Err(e) => {
+                diagnostics.push(units[0].diagnostic(
+                    Severity::Error,
+                    format!("invalid include pattern '{pattern}': {e}"),
+                    include_span,
+                ));
+                continue;
+            }
+        };
+        if files.is_empty() {
+            diagnostics.push(units[0].diagnostic(
+                Severity::Warning,
+                format!("include pattern '{pattern}' matches no file"),
+                include_span.clone(),
+            ));
+        }
+
+        for file in files
+            .into_iter()
+            .filter(|file| normalize(file) != normalize(path))
+        {
+            let namespace = file
+                .file_stem()
+                .map(|stem| stem.to_string_lossy().to_string())
+                .unwrap_or_default();
+            let (mut included, unit) = match read(&file, Some(namespace)) {
+                Ok(parsed) => parsed,
+                Err(diagnostic) => {
+                    diagnostics.push(diagnostic);
+                    continue;
+                }
+            };
+            if !included.include.is_empty() {
+                diagnostics.push(unit.diagnostic(
+                    Severity::Error,
+                    "included catalogues can't include other catalogues".to_string(),
+                    unit.spans.include(),
+                ));
+            }
+            if let Some(hooks) = &unit.spans.hooks {
+                diagnostics.push(unit.diagnostic(
+                    Severity::Error,
+                    "hooks can only be set in info.toml itself".to_string(),
+                    hooks.span(),
+                ));
+            }
+            let namespace = unit.namespace.clone().unwrap_or_default();
+            if namespace.is_empty() || namespace.contains(['/', ' ']) {
+                let span = unit
+                    .spans
+                    .namespace
+                    .as_ref()
+                    .map(|n| n.span())
+                    .unwrap_or_default();
+                diagnostics.push(unit.diagnostic(
+                    Severity::Error,
+                    format!("invalid namespace '{namespace}', it can't be empty or contain '/' or spaces"),
+                    span,
+                ));
+            }
+
+            for exercise in &mut included.exercises {
+                exercise.requires = exercise.requires.iter().map(|r| unit.qualify(r)).collect();
+                exercise.name = unit.qualify(&exercise.name);
+            }
+            for (category, members) in included.categories {
+                list.categories
+                    .entry(category)
+                    .or_default()
+                    .extend(members.iter().map(|m| unit.qualify(m)));
+            }
+            origins.extend((0..included.exercises.len()).map(|i| (units.len(), i)));
+            list.exercises.append(&mut included.exercises);
+            units.push(unit);
+        }
+    }
+
+    diagnostics.extend(check(&list, &units, &origins));
+    if diagnostics.iter().any(Diagnostic::is_error) {
+        return Err(diagnostics);
+    }
+    list.exercises = graph::sort(list.exercises).expect("the graph was checked");
+    Ok((list, diagnostics))
+}
+
+// Read and parse one catalogue file
+fn read(path: &Path, namespace: Option<String>) -> Result<(ExerciseList, Unit), Diagnostic> {
+    let source = fs::read_to_string(path).map_err(|e| {
+        Diagnostic::new(
+            Severity::Error,
+            path,
+            format!("failed to read the catalogue: {e}"),
+        )
+    })?;
+    parse(path, source, namespace)
+}
+
+fn parse(
+    path: &Path,
+    source: String,
+    default_namespace: Option<String>,
+) -> Result<(ExerciseList, Unit), Diagnostic> {
+    let list = toml::from_str::<ExerciseList>(&source).map_err(|e| {
+        Diagnostic::new(Severity::Error, path, e.message().trim().to_string())
+            .at_span(&source, e.span())
+    })?;
+    let spans: Spans =
+        toml::from_str(&source).expect("a catalogue that parsed has the spanned fields");
+    let namespace = default_namespace.map(|default| list.namespace.clone().unwrap_or(default));
+    let local = list.exercises.iter().map(|e| e.name.clone()).collect();
+    let unit = Unit {
+        file: path.to_path_buf(),
+        source,
+        spans,
+        namespace,
+        local,
+    };
+    Ok((list, unit))
+}
+
+fn check(list: &ExerciseList, units: &[Unit], origins: &[(usize, usize)]) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+    let locate = |index: usize| {
+        let (u, e) = origins[index];
+        (&units[u], &units[u].spans.exercises[e])
+    };
+
+    let mut seen: HashMap<&str, usize> = HashMap::new();
+    for (index, exercise) in list.exercises.iter().enumerate() {
+        let (unit, entry) = locate(index);
+        let error =
+            |message: String, span: Range<usize>| unit.diagnostic(Severity::Error, message, span);
+        let name_span = entry.name.span();
+
+        if let Some(first) = seen.insert(&exercise.name, index) {
+            let (first_unit, first_entry) = locate(first);
+            let first_at = first_unit
+                .diagnostic(Severity::Error, String::new(), first_entry.name.span())
+                .position
+                .map(|(line, _)| line)
+                .unwrap_or_default();
+            let location = if std::ptr::eq(first_unit, unit) {
+                format!("line {first_at}")
+            } else {
+                format!("{}:{first_at}", first_unit.file.display())
+            };
+            diagnostics.push(error(
+                format!(
+                    "duplicate exercise name '{}', first defined on {location}",
+                    exercise.name
+                ),
+                name_span.clone(),
+            ));
+        }
+
+        if !inside_exercises(&exercise.path) {
+            diagnostics.push(error(
+                format!(
+                    "'{}' is outside the `{EXERCISES_DIR}/` directory",
+                    exercise.path.display()
+                ),
+                entry.path.span(),
+            ));
+        } else if !exercise.path.is_file() {
+            diagnostics.push(error(
+                format!("'{}' doesn't exist", exercise.path.display()),
+                entry.path.span(),
+            ));
+        }
+
+        let files = [
+            ("hint file", &entry.hint_file),
+            ("template", &entry.template),
+            ("solution", &entry.solution),
+        ];
+        for (kind, file) in files {
+            if let Some(file) = file.as_ref().filter(|f| !f.get_ref().is_file()) {
+                diagnostics.push(error(
+                    format!("{kind} '{}' doesn't exist", file.get_ref().display()),
+                    file.span(),
+                ));
+            }
+        }
+
+        match (&entry.hint, &entry.hints) {
+            (Some(_), Some(hints)) => diagnostics.push(error(
+                format!(
+                    "exercise '{}' sets both `hint` and `hints`, keep one of them",
+                    exercise.name
+                ),
+                hints.span(),
+            )),
+            (Some(_), None) | (None, Some(_)) if entry.hint_file.is_some() => {
+                diagnostics.push(error(
+                    format!(
+                        "exercise '{}' sets `hint_file` and an inline hint, keep one of them",
+                        exercise.name
+                    ),
+                    entry
+                        .hint_file
+                        .as_ref()
+                        .map(|f| f.span())
+                        .unwrap_or_default(),
+                ))
+            }
+            (_, Some(hints)) if hints.get_ref().is_empty() => diagnostics.push(error(
+                format!("exercise '{}' has an empty `hints` list", exercise.name),
+                hints.span(),
+            )),
+            _ => {}
+        }
+        let span = match (&entry.hint, &entry.hints) {
+            (_, Some(hints)) => hints.span(),
+            (Some(hint), None) => hint.span(),
+            (None, None) => entry.name.span(),
+        };
+        if exercise.hint_file().is_none()
+            && exercise.hint_levels().iter().any(|h| h.trim().is_empty())
+        {
+            diagnostics.push(unit.diagnostic(
+                Severity::Warning,
+                format!("exercise '{}' has an empty hint", exercise.name),
+                span,
+            ));
+        }
+
+        for criterion in &exercise.criteria {
+            let problem = match criterion {
+                Criterion::OutputMatches { pattern } => Regex::new(pattern)
+                    .err()
+                    .map(|e| format!("invalid `output_matches` pattern: {e}")),
+                Criterion::Command { command } if command.is_empty() => {
+                    Some("the `command` criterion needs a program to run".to_string())
+                }
+                _ => None,
+            };
+            if let Some(problem) = problem {
+                diagnostics.push(error(
+                    format!("exercise '{}': {problem}", exercise.name),
+                    name_span.clone(),
+                ));
+            }
+        }
+    }
+
+    if let Err(errors) = graph::topological_order(&list.exercises) {
+        for graph_error in errors {
+            let culprit = match &graph_error {
+                GraphError::UnknownRequirement { exercise, .. } => exercise,
+                GraphError::Cycle(names) => &names[0],
+            };
+            let index = seen[culprit.as_str()];
+            let (unit, entry) = locate(index);
+            diagnostics.push(unit.diagnostic(
+                Severity::Error,
+                graph_error.to_string(),
+                entry.name.span(),
+            ));
+        }
+    }
+
+    for unit in units {
+        for (category, members) in &unit.spans.categories {
+            for member in members.get_ref() {
+                let member = unit.qualify(member);
+                if !seen.contains_key(member.as_str()) {
+                    diagnostics.push(unit.diagnostic(
+                        Severity::Error,
+                        format!("category '{category}' lists '{member}', which doesn't exist"),
+                        members.span(),
+                    ));
+                }
+            }
+        }
+    }
+
+    diagnostics.extend(unregistered_files(list).into_iter().map(|path| {
+        Diagnostic::new(
+            Severity::Warning,
+            &units[0].file,
+            format!("'{}' isn't registered by any exercise", path.display()),
+        )
+    }));
+
+    diagnostics
+}
+
+// Whether a relative path stays inside the exercises directory
+fn inside_exercises(path: &Path) -> bool {
+    let mut components = path
+        .components()
+        .filter(|c| !matches!(c, Component::CurDir));
+    components.next() == Some(Component::Normal(EXERCISES_DIR.as_ref()))
+        && components.all(|c| matches!(c, Component::Normal(_)))
+}
+
+// The `.rs` files under the exercises directory that no entry points at,
+// as its exercise, template or solution
+fn unregistered_files(list: &ExerciseList) -> Vec<PathBuf> {
+    let registered: HashSet<PathBuf> = list
+        .exercises
+        .iter()
+        .flat_map(|e| [Some(&e.path), e.template.as_ref(), e.solution.as_ref()])
+        .flatten()
+        .map(|path| normalize(path))
+        .collect();
+    glob(&format!("{EXERCISES_DIR}/**/*.rs"))
+        .map(|paths| {
+            paths
+                .filter_map(Result::ok)
+                .filter(|path| !registered.contains(&normalize(path)))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+// Print every diagnostic and a summary, as `kiga check-config` does
+pub fn print_report(file: &Path, diagnostics: &[Diagnostic]) {
+    for diagnostic in diagnostics {
+        println!("{diagnostic}\n");
+    }
+    let errors = diagnostics.iter().filter(|d| d.is_error()).count();
+    let warnings = diagnostics.len() - errors;
+    if errors == 0 && warnings == 0 {
+        success!("{} is valid", file.display());
+    } else {
+        println!(
+            "{}: {errors} error(s), {warnings} warning(s)",
+            file.display()
+        );
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::category::Selection;
+
+    fn check_source(source: &str) -> Vec<Diagnostic> {
+        let (list, unit) = parse(Path::new("info.toml"), source.to_string(), None).unwrap();
+        let origins: Vec<(usize, usize)> = (0..list.exercises.len()).map(|i| (0, i)).collect();
+        check(&list, &[unit], &origins)
+            .into_iter()
+            .filter(|d| !d.message.contains("isn't registered"))
+            .collect()
+    }
+
+    #[test]
+    fn test_position_of_offset() {
+        let diagnostic = Diagnostic::new(Severity::Error, Path::new("info.toml"), String::new())
+            .at("a = 1\nbb = 2\n", 9);
+        assert_eq!(diagnostic.position, Some((2, 4)));
+        assert_eq!(diagnostic.snippet.as_deref(), Some("bb = 2"));
+    }
+
+    #[test]
+    fn test_unknown_key_is_located() {
+        let source = "[[exercises]]\nname = \"a\"\npath = \"exercises/intro/intro.rs\"\nmode = \"compile\"\nhint = \"h\"\nhnt = \"typo\"\n";
+        let error = toml::from_str::<ExerciseList>(source).unwrap_err();
+        let diagnostic = Diagnostic::new(Severity::Error, Path::new("info.toml"), String::new())
+            .at_span(source, error.span());
+        assert_eq!(diagnostic.position.map(|(line, _)| line), Some(6));
+        assert!(error.message().contains("unknown field `hnt`"));
+    }
+
+    #[test]
+    fn test_semantic_checks() {
+        let source = r#"
+[categories]
+basics = ["intro", "ghost"]
+
+[[exercises]]
+name = "intro"
+path = "exercises/intro/intro.rs"
+mode = "compile"
+hint = "h"
+
+[[exercises]]
+name = "intro"
+path = "../elsewhere.rs"
+mode = "compile"
+hint = "  "
+solution = "solutions/missing.rs"
+"#;
+        let messages: Vec<(Severity, Option<usize>, String)> = check_source(source)
+            .into_iter()
+            .map(|d| (d.severity, d.position.map(|(line, _)| line), d.message))
+            .collect();
+        assert_eq!(
+            messages,
+            [
+                (
+                    Severity::Error,
+                    Some(12),
+                    "duplicate exercise name 'intro', first defined on line 6".to_string()
+                ),
+                (
+                    Severity::Error,
+                    Some(13),
+                    "'../elsewhere.rs' is outside the `exercises/` directory".to_string()
+                ),
+                (
+                    Severity::Error,
+                    Some(16),
+                    "solution 'solutions/missing.rs' doesn't exist".to_string()
+                ),
+                (
+                    Severity::Warning,
+                    Some(15),
+                    "exercise 'intro' has an empty hint".to_string()
+                ),
+                (
+                    Severity::Error,
+                    Some(3),
+                    "category 'basics' lists 'ghost', which doesn't exist".to_string()
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_categories_and_tags() {
+        let (list, _) = load(Path::new("tests/fixture/catalogue/info.toml")).unwrap();
+        assert_eq!(list.categories["concurrency"], ["intro"]);
+        assert_eq!(list.exercises[0].tags, ["threads", "unsafe"]);
+
+        let selection = Selection {
+            tags: vec!["threads".to_string()],
+            categories: vec!["basics".to_string()],
+        };
+        let names: Vec<&str> = selection
+            .apply(&list.exercises, &list.categories)
+            .iter()
+            .map(|e| e.name.as_str())
+            .collect();
+        assert_eq!(names, ["intro", "test"]);
+    }
+
+    #[test]
+    fn test_inside_exercises() {
+        assert!(inside_exercises(Path::new("exercises/intro/intro.rs")));
+        assert!(inside_exercises(Path::new("./exercises/intro.rs")));
+        assert!(!inside_exercises(Path::new("exercises/../src/main.rs")));
+        assert!(!inside_exercises(Path::new("/exercises/intro.rs")));
+        assert!(!inside_exercises(Path::new("src/main.rs")));
+    }
+
+    #[test]
+    fn test_includes_are_namespaced_and_merged() {
+        let dir = std::env::temp_dir().join(format!("kiga_include_{}", std::process::id()));
+        fs::create_dir_all(dir.join("catalogues")).unwrap();
+        let root = dir.join("info.toml");
+        let entry = |name: &str, requires: &str| {
+            format!(
+                "[[exercises]]\nname = \"{name}\"\npath = \"exercises/intro/intro.rs\"\nmode = \"compile\"\nhint = \"h\"\nrequires = [{requires}]\n"
+            )
+        };
+        fs::write(
+            &root,
+            format!(
+                "include = [\"{}/catalogues/*.toml\"]\n{}",
+                dir.display(),
+                entry("intro", "")
+            ),
+        )
+        .unwrap();
+        fs::write(
+            dir.join("catalogues/concurrency.toml"),
+            format!(
+                "[categories]\nthreads = [\"more\"]\n{}{}",
+                entry("threads", "\"intro\""),
+                entry("more", "\"threads\"")
+            ),
+        )
+        .unwrap();
+        fs::write(
+            dir.join("catalogues/other.toml"),
+            format!("namespace = \"concurrency\"\n{}", entry("more", "")),
+        )
+        .unwrap();
+
+        let errors = load(&root).unwrap_err();
+        let messages: Vec<&str> = errors
+            .iter()
+            .filter(|d| d.is_error())
+            .map(|d| d.message.as_str())
+            .collect();
+        assert_eq!(messages.len(), 1);
+        assert!(messages[0]
+            .starts_with("duplicate exercise name 'concurrency/more', first defined on "));
+
+        fs::remove_file(dir.join("catalogues/other.toml")).unwrap();
+        let (list, _) = load(&root).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+
+        let names: Vec<(&str, &[String])> = list
+            .exercises
+            .iter()
+            .map(|e| (e.name.as_str(), e.requires.as_slice()))
+            .collect();
+        assert_eq!(
+            names,
+            [
+                ("intro", &[][..]),
+                ("concurrency/threads", &["intro".to_string()][..]),
+                ("concurrency/more", &["concurrency/threads".to_string()][..]),
+            ]
+        );
+        assert_eq!(list.categories["threads"], ["concurrency/more"]);
+    }
+
+    #[test]
+    fn test_hooks_only_in_info_toml() {
+        let dir = std::env::temp_dir().join(format!("kiga_hooks_{}", std::process::id()));
+        fs::create_dir_all(dir.join("catalogues")).unwrap();
+        let root = dir.join("info.toml");
+        let entry = "[[exercises]]\nname = \"intro\"\npath = \"exercises/intro/intro.rs\"\nmode = \"compile\"\nhint = \"h\"\n";
+        fs::write(
+            &root,
+            format!(
+                "include = [\"{}/catalogues/*.toml\"]\n[hooks]\npost_run = \"true\"\n{entry}",
+                dir.display()
+            ),
+        )
+        .unwrap();
+        fs::write(
+            dir.join("catalogues/extra.toml"),
+            format!("[hooks]\npre_compile = \"true\"\n{entry}"),
+        )
+        .unwrap();
+
+        let errors = load(&root).unwrap_err();
+        let messages: Vec<&str> = errors
+            .iter()
+            .filter(|d| d.is_error())
+            .map(|d| d.message.as_str())
+            .collect();
+        assert_eq!(messages, ["hooks can only be set in info.toml itself"]);
+
+        fs::remove_file(dir.join("catalogues/extra.toml")).unwrap();
+        let (list, _) = load(&root).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(list.hooks.post_run.as_deref(), Some("true"));
+    }
+}
@@ -0,0 +1,168 @@
+use crate::exercise::Exercise;
+use clap::Args;
+use std::collections::BTreeMap;
+
+// The name of the group holding exercises that belong to no category
+pub const UNCATEGORIZED: &str = "uncategorized";
+
+// The `[categories]` table of info.toml, mapping a category name
+// to the names of the exercises it contains, e.g.
+//
+//     [categories]
+//     concurrency = ["intro"]
+pub type Categories = BTreeMap<String, Vec<String>>;
+
+// Restricts a command to the exercises with some tag or in some category
+#[derive(Args, Clone, Debug, Default)]
+pub struct Selection {
+    /// Select only exercises with the given tag, may be repeated
+    #[arg(long = "tag")]
+    pub tags: Vec<String>,
+    /// Select only exercises in the given category, may be repeated
+    #[arg(long = "category")]
+    pub categories: Vec<String>,
+}
+
+impl Selection {
+    pub fn is_empty(&self) -> bool {
+        self.tags.is_empty() && self.categories.is_empty()
+    }
+
+    // Whether an exercise is selected. Tags and categories are alternatives,
+    // so `--tag a --category b` selects exercises matching either
+    pub fn matches(&self, exercise: &Exercise, categories: &Categories) -> bool {
+        self.is_empty()
+            || self.tags.iter().any(|t| exercise.tags.contains(t))
+            || self.categories.iter().any(|c| {
+                categories
+                    .get(c)
+                    .is_some_and(|names| names.contains(&exercise.name))
+            })
+    }
+
+    // The selected exercises in their original order
+    pub fn apply<'a>(
+        &self,
+        exercises: &'a [Exercise],
+        categories: &Categories,
+    ) -> Vec<&'a Exercise> {
+        exercises
+            .iter()
+            .filter(|e| self.matches(e, categories))
+            .collect()
+    }
+
+    // The first requested category that the catalogue doesn't define
+    pub fn unknown_category<'s>(&'s self, categories: &Categories) -> Option<&'s str> {
+        self.categories
+            .iter()
+            .find(|c| !categories.contains_key(*c))
+            .map(String::as_str)
+    }
+}
+
+// Split exercises into their categories, keeping their order within each group.
+// An exercise listed in several categories appears in each of them, and the
+// exercises in no category are gathered in a final `uncategorized` group
+pub fn group<'a, 'c, T>(
+    items: &'a [T],
+    name_of: impl Fn(&T) -> &str,
+    categories: &'c Categories,
+) -> Vec<(&'c str, Vec<&'a T>)> {
+    let mut groups: Vec<(&str, Vec<&T>)> = categories
+        .iter()
+        .map(|(category, names)| {
+            let members = items
+                .iter()
+                .filter(|item| names.iter().any(|n| n == name_of(item)))
+                .collect();
+            (category.as_str(), members)
+        })
+        .filter(|(_, members): &(&str, Vec<&T>)| !members.is_empty())
+        .collect();
+
+    let uncategorized: Vec<&T> = items
+        .iter()
+        .filter(|item| !categories.values().flatten().any(|n| n == name_of(item)))
+        .collect();
+    if !uncategorized.is_empty() {
+        groups.push((UNCATEGORIZED, uncategorized));
+    }
+    groups
+}
+
+// Print how many exercises of each category are done
+pub fn print_progress(exercises: &[&Exercise], categories: &Categories) {
+    if categories.is_empty() {
+        return;
+    }
+    println!("Progress by category:");
+    for (category, members) in group(exercises, |e| &e.name, categories) {
+        let done = members.iter().filter(|e| e.looks_done()).count();
+        println!(
+            "  {category:<20} {done} / {} ({:.1} %)",
+            members.len(),
+            done as f32 / members.len() as f32 * 100.0
+        );
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn categories() -> Categories {
+        Categories::from([
+            ("concurrency".to_string(), vec!["threads".to_string()]),
+            (
+                "basics".to_string(),
+                vec!["intro".to_string(), "threads".to_string()],
+            ),
+        ])
+    }
+
+    #[test]
+    fn test_selection_by_tag_or_category() {
+        let exercises = [
+            Exercise::named("intro").tagged(&["easy"]),
+            Exercise::named("threads"),
+            Exercise::named("misc").tagged(&["easy"]),
+        ];
+        let selection = Selection {
+            tags: vec![],
+            categories: vec!["concurrency".to_string()],
+        };
+        let names: Vec<&str> = selection
+            .apply(&exercises, &categories())
+            .iter()
+            .map(|e| e.name.as_str())
+            .collect();
+        assert_eq!(names, ["threads"]);
+
+        let selection = Selection {
+            tags: vec!["easy".to_string()],
+            categories: vec!["concurrency".to_string()],
+        };
+        assert_eq!(selection.apply(&exercises, &categories()).len(), 3);
+        assert_eq!(selection.unknown_category(&categories()), None);
+    }
+
+    #[test]
+    fn test_group_keeps_uncategorized_last() {
+        let names = ["intro", "misc", "threads"];
+        let categories = categories();
+        let groups = group(&names, |n| *n, &categories);
+        let groups: Vec<(&str, Vec<&str>)> = groups
+            .into_iter()
+            .map(|(c, members)| (c, members.into_iter().copied().collect()))
+            .collect();
+        assert_eq!(
+            groups,
+            [
+                ("basics", vec!["intro", "threads"]),
+                ("concurrency", vec!["threads"]),
+                (UNCATEGORIZED, vec!["misc"]),
+            ]
+        );
+    }
+}
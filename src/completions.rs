@@ -0,0 +1,83 @@
+use crate::catalogue;
+use crate::lookup::ALIASES;
+use crate::root::Root;
+use clap::ValueEnum;
+use clap_complete::env::{Bash, Elvish, EnvCompleter, Fish, Powershell, Zsh};
+use clap_complete::CompletionCandidate;
+use std::env;
+use std::io;
+
+// Set by the generated scripts when they ask kiga for completions
+pub const COMPLETE_ENV: &str = "KIGA_COMPLETE";
+
+// The shells `kiga completions` can generate a script for
+#[derive(ValueEnum, Copy, Clone, Debug, PartialEq)]
+pub enum Shell {
+    Bash,
+    Zsh,
+    Fish,
+    Powershell,
+    Elvish,
+}
+
+impl Shell {
+    fn completer(self) -> &'static dyn EnvCompleter {
+        match self {
+            Shell::Bash => &Bash,
+            Shell::Zsh => &Zsh,
+            Shell::Fish => &Fish,
+            Shell::Powershell => &Powershell,
+            Shell::Elvish => &Elvish,
+        }
+    }
+}
+
+// Write the completion script for a shell. The script doesn't list the
+// exercises itself: it calls back into this binary on every <TAB>, so names
+// always come from the catalogue of the project you're in
+pub fn write(shell: Shell, out: &mut dyn io::Write) -> io::Result<()> {
+    let completer = env::current_exe()?;
+    shell.completer().write_registration(
+        COMPLETE_ENV,
+        "kiga",
+        "kiga",
+        &completer.to_string_lossy(),
+        out,
+    )
+}
+
+// The exercise names of the project containing the current directory,
+// with the aliases accepted wherever a name is
+pub fn exercise_names() -> Vec<CompletionCandidate> {
+    let mut candidates: Vec<CompletionCandidate> = ALIASES
+        .iter()
+        .map(|alias| CompletionCandidate::new(*alias).help(Some("alias".into())))
+        .collect();
+    let Ok(root) = Root::discover(None) else {
+        return candidates;
+    };
+    if root.enter().is_err() {
+        return candidates;
+    }
+    if let Ok((list, _)) = catalogue::load(&root.manifest) {
+        candidates.extend(list.exercises.iter().map(|e| {
+            CompletionCandidate::new(&e.name).help(Some(e.path.display().to_string().into()))
+        }));
+    }
+    candidates
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_scripts_call_back_into_kiga() {
+        for shell in Shell::value_variants() {
+            let mut script = Vec::new();
+            write(*shell, &mut script).unwrap();
+            let script = String::from_utf8(script).unwrap();
+            assert!(script.contains(COMPLETE_ENV), "{shell:?}: {script}");
+        }
+    }
+}
@@ -0,0 +1,561 @@
+use std::collections::BTreeMap;
+use std::env;
+use std::fmt::{self, Display, Formatter};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+use toml::{Table, Value};
+
+// The project-level configuration file, next to info.toml
+pub const PROJECT_CONFIG: &str = ".kiga.toml";
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+enum Kind {
+    Bool,
+    Integer,
+    Text,
+}
+
+// The value a setting has when nothing configures it
+#[derive(Copy, Clone, Debug)]
+enum Fallback {
+    Bool(bool),
+    Integer(i64),
+    Unset,
+}
+
+// A configurable knob. Environment variables named `NO_*` switch a
+// boolean setting off by merely being set, like the classic `NO_EMOJI`
+struct Setting {
+    key: &'static str,
+    kind: Kind,
+    fallback: Fallback,
+    env: &'static [&'static str],
+    description: &'static str,
+}
+
+const SETTINGS: &[Setting] = &[
+    Setting {
+        key: "emoji",
+        kind: Kind::Bool,
+        fallback: Fallback::Bool(true),
+        env: &["KIGA_EMOJI", "NO_EMOJI"],
+        description: "Decorate messages with emoji",
+    },
+    Setting {
+        key: "colors",
+        kind: Kind::Bool,
+        fallback: Fallback::Bool(true),
+        env: &["KIGA_COLORS", "NO_COLOR"],
+        description: "Color the output",
+    },
+    Setting {
+        key: "verbose",
+        kind: Kind::Bool,
+        fallback: Fallback::Bool(false),
+        env: &["KIGA_VERBOSE"],
+        description: "Show outputs from the test exercises, like --nocapture",
+    },
+    Setting {
+        key: "success_hints",
+        kind: Kind::Bool,
+        fallback: Fallback::Bool(false),
+        env: &["KIGA_SUCCESS_HINTS"],
+        description: "Show hints on success in watch mode, like --success-hints",
+    },
+    Setting {
+        key: "watch.debounce_ms",
+        kind: Kind::Integer,
+        fallback: Fallback::Integer(1000),
+        env: &["KIGA_WATCH_DEBOUNCE_MS"],
+        description: "How long watch mode waits for edits to settle",
+    },
+    Setting {
+        key: "watch.poll_ms",
+        kind: Kind::Integer,
+        fallback: Fallback::Unset,
+        env: &["KIGA_WATCH_POLL_MS"],
+        description: "Poll for changes this often instead of using file system events",
+    },
+    Setting {
+        key: "toolchain",
+        kind: Kind::Text,
+        fallback: Fallback::Unset,
+        env: &["KIGA_TOOLCHAIN"],
+        description: "The rustup toolchain used to build exercises",
+    },
+    Setting {
+        key: "rust_src_path",
+        kind: Kind::Text,
+        fallback: Fallback::Unset,
+        env: &["RUST_SRC_PATH"],
+        description: "The standard library sources used by `kiga lsp`",
+    },
+    Setting {
+        key: "editor",
+        kind: Kind::Text,
+        fallback: Fallback::Unset,
+        env: &["KIGA_EDITOR", "VISUAL", "EDITOR"],
+        description: "The command that opens an exercise in your editor",
+    },
+    Setting {
+        key: "notify.passed",
+        kind: Kind::Text,
+        fallback: Fallback::Unset,
+        env: &["KIGA_NOTIFY_PASSED"],
+        description: "How watch mode signals a passing check: bell, osc9, osc777 and/or command",
+    },
+    Setting {
+        key: "notify.failed",
+        kind: Kind::Text,
+        fallback: Fallback::Unset,
+        env: &["KIGA_NOTIFY_FAILED"],
+        description: "How watch mode signals a check that didn't pass",
+    },
+    Setting {
+        key: "notify.next",
+        kind: Kind::Text,
+        fallback: Fallback::Unset,
+        env: &["KIGA_NOTIFY_NEXT"],
+        description: "How watch mode signals moving on to the next exercise",
+    },
+    Setting {
+        key: "notify.command",
+        kind: Kind::Text,
+        fallback: Fallback::Unset,
+        env: &["KIGA_NOTIFY_COMMAND"],
+        description: "The command run by the `command` notification, like notify-send",
+    },
+    Setting {
+        key: "hooks.pre_compile",
+        kind: Kind::Text,
+        fallback: Fallback::Unset,
+        env: &["KIGA_HOOKS_PRE_COMPILE"],
+        description: "A shell command run before an exercise is built",
+    },
+    Setting {
+        key: "hooks.post_compile",
+        kind: Kind::Text,
+        fallback: Fallback::Unset,
+        env: &["KIGA_HOOKS_POST_COMPILE"],
+        description: "A shell command run after an exercise is built",
+    },
+    Setting {
+        key: "hooks.post_run",
+        kind: Kind::Text,
+        fallback: Fallback::Unset,
+        env: &["KIGA_HOOKS_POST_RUN"],
+        description: "A shell command run after an exercise is run",
+    },
+    Setting {
+        key: "hooks.on_success",
+        kind: Kind::Text,
+        fallback: Fallback::Unset,
+        env: &["KIGA_HOOKS_ON_SUCCESS"],
+        description: "A shell command run when an exercise passes",
+    },
+    Setting {
+        key: "hooks.on_failure",
+        kind: Kind::Text,
+        fallback: Fallback::Unset,
+        env: &["KIGA_HOOKS_ON_FAILURE"],
+        description: "A shell command run when an exercise doesn't pass",
+    },
+    Setting {
+        key: "hooks.on_all_done",
+        kind: Kind::Text,
+        fallback: Fallback::Unset,
+        env: &["KIGA_HOOKS_ON_ALL_DONE"],
+        description: "A shell command run when watch mode finds every exercise done",
+    },
+];
+
+fn setting(key: &str) -> Option<&'static Setting> {
+    SETTINGS.iter().find(|s| s.key == key)
+}
+
+// All keys, for error messages
+pub fn keys() -> Vec<&'static str> {
+    SETTINGS.iter().map(|s| s.key).collect()
+}
+
+// Where the effective value of a setting comes from
+#[derive(Clone, Debug, PartialEq)]
+pub enum Source {
+    Default,
+    File(PathBuf),
+    Env(&'static str),
+    Cli(&'static str),
+}
+
+impl Display for Source {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            Source::Default => write!(f, "default"),
+            Source::File(path) => write!(f, "{}", path.display()),
+            Source::Env(var) => write!(f, "environment variable {var}"),
+            Source::Cli(flag) => write!(f, "command line flag {flag}"),
+        }
+    }
+}
+
+// The effective configuration: defaults, overridden by the user file
+// (`~/.config/kiga/config.toml`), then the project's `.kiga.toml`,
+// then environment variables, then command line flags
+#[derive(Debug)]
+pub struct Config {
+    values: BTreeMap<&'static str, (Value, Source)>,
+    // Problems found while reading the layers, reported once at startup
+    pub warnings: Vec<String>,
+}
+
+static CONFIG: OnceLock<Config> = OnceLock::new();
+
+// Make `config` the configuration returned by `get`. This has to happen
+// before anything reads the configuration, or the CLI overrides would be lost
+pub fn init(config: Config) {
+    if CONFIG.set(config).is_err() {
+        panic!("the configuration was read before config::init");
+    }
+}
+
+// The process-wide configuration, loaded without CLI overrides if `init`
+// wasn't called
+pub fn get() -> &'static Config {
+    CONFIG.get_or_init(|| Config::load(&[]))
+}
+
+// The user-level configuration file
+pub fn user_config_path() -> Option<PathBuf> {
+    env::var_os("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .filter(|dir| dir.is_absolute())
+        .or_else(|| home::home_dir().map(|home| home.join(".config")))
+        .map(|dir| dir.join("kiga").join("config.toml"))
+}
+
+impl Config {
+    fn defaults() -> Config {
+        let values = SETTINGS
+            .iter()
+            .filter_map(|s| {
+                let value = match s.fallback {
+                    Fallback::Bool(b) => Value::Boolean(b),
+                    Fallback::Integer(i) => Value::Integer(i),
+                    Fallback::Unset => return None,
+                };
+                Some((s.key, (value, Source::Default)))
+            })
+            .collect();
+        Config {
+            values,
+            warnings: Vec::new(),
+        }
+    }
+
+    // Load every layer. `cli` lists the settings given as flags,
+    // as (key, value, flag) triples
+    pub fn load(cli: &[(&'static str, Value, &'static str)]) -> Config {
+        let mut config = Config::defaults();
+        if let Some(path) = user_config_path() {
+            config.merge_file(&path);
+        }
+        config.merge_file(Path::new(PROJECT_CONFIG));
+        config.merge_env(|var| env::var(var).ok());
+        for (key, value, flag) in cli {
+            config
+                .values
+                .insert(key, (value.clone(), Source::Cli(flag)));
+        }
+        config
+    }
+
+    fn merge_file(&mut self, path: &Path) {
+        let Ok(source) = fs::read_to_string(path) else {
+            return;
+        };
+        let table = match source.parse::<Table>() {
+            Ok(table) => table,
+            Err(e) => {
+                self.warnings.push(format!(
+                    "Ignoring {}: {}",
+                    path.display(),
+                    e.message().trim()
+                ));
+                return;
+            }
+        };
+        let mut entries = Vec::new();
+        flatten("", table, &mut entries);
+        for (key, value) in entries {
+            let Some(setting) = setting(&key) else {
+                self.warnings
+                    .push(format!("Unknown setting '{key}' in {}", path.display()));
+                continue;
+            };
+            if kind_of(&value) != Some(setting.kind) {
+                self.warnings.push(format!(
+                    "Ignoring '{key}' in {}: expected {}",
+                    path.display(),
+                    kind_name(setting.kind)
+                ));
+                continue;
+            }
+            self.values
+                .insert(setting.key, (value, Source::File(path.to_path_buf())));
+        }
+    }
+
+    fn merge_env(&mut self, var: impl Fn(&str) -> Option<String>) {
+        for setting in SETTINGS {
+            let found = setting
+                .env
+                .iter()
+                .find_map(|name| var(name).map(|value| (*name, value)));
+            let Some((name, raw)) = found else {
+                continue;
+            };
+            let value = if name.starts_with("NO_") {
+                Some(Value::Boolean(false))
+            } else {
+                parse_value(setting.kind, &raw).ok()
+            };
+            match value {
+                Some(value) => {
+                    self.values.insert(setting.key, (value, Source::Env(name)));
+                }
+                None => self.warnings.push(format!(
+                    "Ignoring {name}={raw}: expected {}",
+                    kind_name(setting.kind)
+                )),
+            }
+        }
+    }
+
+    // The effective value of a setting and where it comes from
+    pub fn lookup(&self, key: &str) -> Option<&(Value, Source)> {
+        self.values.get(key)
+    }
+
+    fn bool(&self, key: &str) -> bool {
+        matches!(self.lookup(key), Some((Value::Boolean(true), _)))
+    }
+
+    fn text(&self, key: &str) -> Option<&str> {
+        self.lookup(key).and_then(|(value, _)| value.as_str())
+    }
+
+    pub fn emoji(&self) -> bool {
+        self.bool("emoji")
+    }
+
+    // Whether colors were turned on or off. Left at its default, console
+    // decides from the terminal
+    pub fn colors(&self) -> Option<bool> {
+        self.lookup("colors")
+            .filter(|(_, source)| *source != Source::Default)
+            .and_then(|(value, _)| value.as_bool())
+    }
+
+    pub fn verbose(&self) -> bool {
+        self.bool("verbose")
+    }
+
+    pub fn success_hints(&self) -> bool {
+        self.bool("success_hints")
+    }
+
+    pub fn watch_debounce_ms(&self) -> u64 {
+        self.lookup("watch.debounce_ms")
+            .and_then(|(value, _)| value.as_integer())
+            .map_or(1000, |ms| ms.max(0) as u64)
+    }
+
+    // How often watch mode polls for changes, when it polls rather than
+    // listening for file system events
+    pub fn watch_poll_ms(&self) -> Option<u64> {
+        self.lookup("watch.poll_ms")
+            .and_then(|(value, _)| value.as_integer())
+            .map(|ms| ms.max(1) as u64)
+    }
+
+    pub fn toolchain(&self) -> Option<&str> {
+        self.text("toolchain")
+    }
+
+    pub fn rust_src_path(&self) -> Option<&str> {
+        self.text("rust_src_path")
+    }
+
+    pub fn editor(&self) -> Option<&str> {
+        self.text("editor")
+    }
+
+    // The notification methods for a watch mode event (passed, failed or
+    // next), as the comma separated list the user wrote
+    pub fn notify_on(&self, event: &str) -> Option<&str> {
+        self.text(&format!("notify.{event}"))
+    }
+
+    pub fn notify_command(&self) -> Option<&str> {
+        self.text("notify.command")
+    }
+
+    // The command of a hook like `post_run`, overriding the one in info.toml
+    pub fn hook(&self, name: &str) -> Option<&str> {
+        self.text(&format!("hooks.{name}"))
+    }
+
+    // Print every setting with its effective value and source
+    pub fn print(&self) {
+        for setting in SETTINGS {
+            match self.lookup(setting.key) {
+                Some((value, source)) => {
+                    println!("{} = {value}    # {source}", setting.key)
+                }
+                None => println!("# {} is not set    # {}", setting.key, setting.description),
+            }
+        }
+    }
+}
+
+// Turn nested tables into dotted keys, so `[watch] debounce_ms = 1`
+// becomes `watch.debounce_ms`
+fn flatten(prefix: &str, table: Table, out: &mut Vec<(String, Value)>) {
+    for (key, value) in table {
+        let key = if prefix.is_empty() {
+            key
+        } else {
+            format!("{prefix}.{key}")
+        };
+        match value {
+            Value::Table(table) => flatten(&key, table, out),
+            value => out.push((key, value)),
+        }
+    }
+}
+
+fn kind_of(value: &Value) -> Option<Kind> {
+    match value {
+        Value::Boolean(_) => Some(Kind::Bool),
+        Value::Integer(_) => Some(Kind::Integer),
+        Value::String(_) => Some(Kind::Text),
+        _ => None,
+    }
+}
+
+fn kind_name(kind: Kind) -> &'static str {
+    match kind {
+        Kind::Bool => "true or false",
+        Kind::Integer => "an integer",
+        Kind::Text => "a string",
+    }
+}
+
+fn parse_value(kind: Kind, raw: &str) -> Result<Value, String> {
+    let invalid = || format!("'{raw}' isn't valid, expected {}", kind_name(kind));
+    match kind {
+        Kind::Bool => match raw.to_lowercase().as_str() {
+            "1" | "true" | "yes" | "on" => Ok(Value::Boolean(true)),
+            "0" | "false" | "no" | "off" => Ok(Value::Boolean(false)),
+            _ => Err(invalid()),
+        },
+        Kind::Integer => raw.parse().map(Value::Integer).map_err(|_| invalid()),
+        Kind::Text => Ok(Value::String(raw.to_string())),
+    }
+}
+
+// Write a setting to a configuration file, keeping its other settings
+pub fn set(path: &Path, key: &str, raw: &str) -> Result<(), String> {
+    let setting = setting(key).ok_or_else(|| unknown_key(key))?;
+    let value = parse_value(setting.kind, raw)?;
+
+    let mut table = match fs::read_to_string(path) {
+        Ok(source) => source
+            .parse::<Table>()
+            .map_err(|e| format!("Can't parse {}: {}", path.display(), e.message().trim()))?,
+        Err(_) => Table::new(),
+    };
+    let mut parts: Vec<&str> = key.split('.').collect();
+    let last = parts.pop().expect("keys aren't empty");
+    let mut current = &mut table;
+    for part in parts {
+        current = match current
+            .entry(part)
+            .or_insert_with(|| Value::Table(Table::new()))
+        {
+            Value::Table(table) => table,
+            _ => return Err(format!("'{part}' in {} isn't a table", path.display())),
+        };
+    }
+    current.insert(last.to_string(), value);
+
+    if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+        fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+    }
+    let source = toml::to_string_pretty(&table).map_err(|e| e.to_string())?;
+    fs::write(path, source).map_err(|e| format!("Can't write {}: {e}", path.display()))
+}
+
+pub fn unknown_key(key: &str) -> String {
+    format!(
+        "Unknown setting '{key}', expected one of: {}",
+        keys().join(", ")
+    )
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_layers_override_each_other() {
+        let dir = env::temp_dir().join(format!("kiga_config_{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let user = dir.join("user.toml");
+        let project = dir.join("project.toml");
+        fs::write(
+            &user,
+            "emoji = false\ncolors = false\n[watch]\ndebounce_ms = 200\n",
+        )
+        .unwrap();
+        fs::write(&project, "colors = true\nverbose = \"yes\"\nbogus = 1\n").unwrap();
+
+        let mut config = Config::defaults();
+        config.merge_file(&user);
+        config.merge_file(&project);
+        config.merge_env(|var| match var {
+            "NO_EMOJI" => Some(String::new()),
+            "KIGA_EDITOR" => Some("nvim".to_string()),
+            _ => None,
+        });
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert!(!config.emoji());
+        assert_eq!(config.lookup("emoji").unwrap().1, Source::Env("NO_EMOJI"));
+        assert_eq!(config.colors(), Some(true));
+        assert_eq!(config.lookup("colors").unwrap().1, Source::File(project));
+        assert_eq!(config.watch_debounce_ms(), 200);
+        assert_eq!(config.editor(), Some("nvim"));
+        assert!(!config.verbose());
+        assert_eq!(config.lookup("verbose").unwrap().1, Source::Default);
+        assert_eq!(config.warnings.len(), 2);
+    }
+
+    #[test]
+    fn test_set_writes_nested_keys() {
+        let path = env::temp_dir()
+            .join(format!("kiga_config_set_{}", std::process::id()))
+            .join("config.toml");
+        set(&path, "emoji", "off").unwrap();
+        set(&path, "watch.debounce_ms", "250").unwrap();
+        assert!(set(&path, "watch.debounce_ms", "soon").is_err());
+        assert!(set(&path, "nope", "1").is_err());
+
+        let mut config = Config::defaults();
+        config.merge_file(&path);
+        fs::remove_dir_all(path.parent().unwrap()).unwrap();
+        assert!(!config.emoji());
+        assert_eq!(config.watch_debounce_ms(), 250);
+    }
+}
@@ -0,0 +1,54 @@
+use serde::Deserialize;
+use std::fmt::{self, Display, Formatter};
+
+// A single rule deciding whether an exercise is done.
+// Rules are listed per exercise in info.toml, e.g.
+//
+//     [[exercises.criteria]]
+//     kind = "output_matches"
+//     pattern = "^42$"
+//
+// An exercise without any rule falls back to `marker_absent`.
+#[derive(Deserialize, Clone, Debug, PartialEq)]
+#[serde(tag = "kind", rename_all = "snake_case", deny_unknown_fields)]
+pub enum Criterion {
+    // The `I AM NOT DONE` comment has been removed from the source
+    MarkerAbsent,
+    // The `I AM NOT DONE` comment is still in the source
+    MarkerPresent,
+    // The exercise compiles in its mode
+    Compiles,
+    // The compiled exercise runs (or its tests pass) without errors
+    TestsPass,
+    // The standard output of the compiled exercise matches a regex
+    OutputMatches { pattern: String },
+    // A custom predicate command exits successfully.
+    // The command is given as an argument vector and receives
+    // `KIGA_EXERCISE` and `KIGA_PATH` in its environment
+    Command { command: Vec<String> },
+}
+
+impl Display for Criterion {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            Criterion::MarkerAbsent => write!(f, "the `I AM NOT DONE` comment is removed"),
+            Criterion::MarkerPresent => write!(f, "the `I AM NOT DONE` comment is present"),
+            Criterion::Compiles => write!(f, "the code compiles"),
+            Criterion::TestsPass => write!(f, "the code runs and its tests pass"),
+            Criterion::OutputMatches { pattern } => {
+                write!(f, "the output matches `{pattern}`")
+            }
+            Criterion::Command { command } => {
+                write!(f, "the command `{}` succeeds", command.join(" "))
+            }
+        }
+    }
+}
+
+impl Criterion {
+    // Whether the rule is decided by reading the source alone, without
+    // building the exercise or running a command
+    pub fn is_static(&self) -> bool {
+        matches!(self, Criterion::MarkerAbsent | Criterion::MarkerPresent)
+    }
+}
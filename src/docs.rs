@@ -0,0 +1,187 @@
+use crate::shell::WATCH_COMMANDS;
+use clap::{Arg, Command};
+use clap_mangen::roff::{bold, roman, Roff};
+use clap_mangen::Man;
+use std::fs;
+use std::io::{self, Write};
+use std::path::Path;
+
+// Write the manual page of a command, with the watch mode commands
+// appended to the pages of `kiga` and `kiga watch`
+pub fn write_manpage(cmd: &Command, out: &mut dyn Write) -> io::Result<()> {
+    Man::new(cmd.clone()).render(out)?;
+    if has_watch_commands(cmd) {
+        let mut roff = Roff::new();
+        roff.control("SH", ["WATCH MODE COMMANDS"]);
+        roff.text([roman(
+            "While `kiga watch` runs, type one of these commands and press enter:",
+        )]);
+        for (command, description) in WATCH_COMMANDS {
+            roff.control("TP", []);
+            roff.text([bold(*command)]);
+            roff.text([roman(*description)]);
+        }
+        out.write_all(roff.to_roff().as_bytes())?;
+    }
+    Ok(())
+}
+
+// Write one page per command of the tree, e.g. `kiga.1` and `kiga-run.1`
+pub fn write_manpages(cmd: &Command, dir: &Path) -> io::Result<()> {
+    fs::create_dir_all(dir)?;
+    let mut file = fs::File::create(dir.join(Man::new(cmd.clone()).get_filename()))?;
+    write_manpage(cmd, &mut file)?;
+    for sub in visible_subcommands(cmd) {
+        write_manpages(sub, dir)?;
+    }
+    Ok(())
+}
+
+// Render the whole command tree as a markdown reference
+pub fn markdown(cmd: &Command) -> String {
+    let mut out = String::new();
+    render_markdown(cmd, &[], &mut out);
+    out
+}
+
+// `parents` are the names of the commands above this one, from the root
+fn render_markdown(cmd: &Command, parents: &[&str], out: &mut String) {
+    let mut names = parents.to_vec();
+    names.push(cmd.get_name());
+    let level = names.len();
+    out.push_str(&format!("{} `{}`\n\n", "#".repeat(level), names.join(" ")));
+    if let Some(about) = cmd.get_long_about().or(cmd.get_about()) {
+        out.push_str(&format!("{about}\n\n"));
+    }
+    let usage = cmd.clone().render_usage().to_string();
+    let usage = usage.trim_start_matches("Usage:").trim();
+    out.push_str(&format!("**Usage:** `{usage}`\n\n"));
+
+    let subcommands: Vec<&Command> = visible_subcommands(cmd).collect();
+    if !subcommands.is_empty() {
+        out.push_str("**Commands:**\n\n");
+        for sub in &subcommands {
+            let about = sub.get_about().map(|a| a.to_string()).unwrap_or_default();
+            out.push_str(&format!("* `{}` — {about}\n", sub.get_name()));
+        }
+        out.push('\n');
+    }
+
+    let args: Vec<&Arg> = cmd.get_arguments().filter(|a| !a.is_hide_set()).collect();
+    if !args.is_empty() {
+        out.push_str("**Arguments and options:**\n\n");
+        for arg in args {
+            out.push_str(&format!("* `{}`", arg_synopsis(arg)));
+            if let Some(help) = arg.get_long_help().or(arg.get_help()) {
+                out.push_str(&format!(" — {}", help.to_string().replace('\n', " ")));
+            }
+            let values: Vec<String> = arg
+                .get_possible_values()
+                .iter()
+                .filter(|v| !v.is_hide_set())
+                .map(|v| format!("`{}`", v.get_name()))
+                .collect();
+            if !values.is_empty() {
+                out.push_str(&format!(" (one of {})", values.join(", ")));
+            }
+            out.push('\n');
+        }
+        out.push('\n');
+    }
+
+    if has_watch_commands(cmd) {
+        out.push_str("**Watch mode commands:**\n\n");
+        for (command, description) in WATCH_COMMANDS {
+            out.push_str(&format!("* `{command}` — {description}\n"));
+        }
+        out.push('\n');
+    }
+
+    for sub in subcommands {
+        render_markdown(sub, &names, out);
+    }
+}
+
+// How an argument is written on the command line, e.g. `--sort <SORT>`
+fn arg_synopsis(arg: &Arg) -> String {
+    let value = arg
+        .get_value_names()
+        .and_then(|names| names.first())
+        .map(|name| name.to_string())
+        .unwrap_or_else(|| arg.get_id().as_str().to_uppercase());
+    if arg.is_positional() {
+        return format!("<{value}>");
+    }
+    let mut names = Vec::new();
+    if let Some(short) = arg.get_short() {
+        names.push(format!("-{short}"));
+    }
+    if let Some(long) = arg.get_long() {
+        names.push(format!("--{long}"));
+    }
+    let takes_value = arg.get_num_args().is_some_and(|n| n.takes_values());
+    if takes_value {
+        format!("{} <{value}>", names.join(", "))
+    } else {
+        names.join(", ")
+    }
+}
+
+fn visible_subcommands(cmd: &Command) -> impl Iterator<Item = &Command> {
+    cmd.get_subcommands().filter(|sub| !sub.is_hide_set())
+}
+
+// Once the command is built, subcommands are named after their parents,
+// e.g. `kiga-watch`
+fn has_watch_commands(cmd: &Command) -> bool {
+    let name = cmd.get_display_name().unwrap_or(cmd.get_name());
+    matches!(name, "kiga" | "kiga-watch")
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::Args;
+    use clap::CommandFactory;
+
+    fn command() -> Command {
+        let mut cmd = Args::command();
+        cmd.build();
+        cmd
+    }
+
+    #[test]
+    fn test_markdown_covers_the_tree() {
+        let markdown = markdown(&command());
+        assert!(markdown.starts_with("# `kiga`\n"));
+        assert!(markdown.contains("## `kiga run`\n"));
+        assert!(markdown.contains("### `kiga config set`\n"));
+        assert!(markdown.contains("* `--sort <SORT>`"));
+        assert!(markdown.contains("* `hint` — "));
+    }
+
+    #[test]
+    fn test_markdown_keeps_hyphenated_names() {
+        let markdown = markdown(&command());
+        let section = markdown
+            .split("\n## ")
+            .find(|s| s.starts_with("`kiga check-config`\n"))
+            .unwrap();
+        assert!(section.contains("**Usage:** `kiga check-config"));
+        assert!(!markdown.contains("`kiga check config`"));
+    }
+
+    #[test]
+    fn test_manpage_lists_watch_commands() {
+        let cmd = command();
+        let mut page = Vec::new();
+        write_manpage(&cmd, &mut page).unwrap();
+        let page = String::from_utf8(page).unwrap();
+        assert!(page.contains(".SH \"WATCH MODE COMMANDS\""));
+
+        let run = cmd.find_subcommand("run").unwrap();
+        let mut page = Vec::new();
+        write_manpage(run, &mut page).unwrap();
+        assert!(!String::from_utf8(page).unwrap().contains("WATCH MODE"));
+    }
+}
@@ -1,11 +1,19 @@
+use crate::category::Categories;
+use crate::config;
+use crate::criteria::Criterion;
+use crate::hooks::Hooks;
+use crate::job;
+use clap::ValueEnum;
+use lazy_static::lazy_static;
 use regex::Regex;
 use serde::Deserialize;
-use std::env;
 use std::fmt::{self, Display, Formatter};
-use std::fs::{self, remove_file, File};
-use std::io::Read;
-use std::path::PathBuf;
+use std::fs::{self, remove_file};
+use std::io;
+use std::path::{Path, PathBuf};
 use std::process::{self, Command};
+use std::sync::Mutex;
+use std::time::SystemTime;
 
 const RUSTC_COLOR_ARGS: &[&str] = &["--color", "always"];
 const RUSTC_EDITION_ARGS: &[&str] = &["--edition", "2021"];
@@ -13,6 +21,12 @@ const RUSTC_NO_DEBUG_ARGS: &[&str] = &["-C", "strip=debuginfo"];
 const I_AM_DONE_REGEX: &str = r"(?m)^\s*///?\s*I\s+AM\s+NOT\s+DONE";
 const CONTEXT: usize = 2;
 const CLIPPY_CARGO_TOML_PATH: &str = "./exercises/clippy/Cargo.toml";
+const HINT_EXTENSION: &str = "md";
+const HINT_FILE: &str = "HINT.md";
+
+lazy_static! {
+    static ref I_AM_DONE: Regex = Regex::new(I_AM_DONE_REGEX).unwrap();
+}
 
 // Get a temporary file name that is hopefully unique
 #[inline]
@@ -26,10 +40,11 @@ fn temp_file() -> String {
 }
 
 // The mode of the exercise.
-#[derive(Deserialize, Copy, Clone, Debug)]
+#[derive(Deserialize, ValueEnum, Copy, Clone, Debug, Default, PartialEq)]
 #[serde(rename_all = "lowercase")]
 pub enum Mode {
     // Indicates that the exercise should be compiled as a binary
+    #[default]
     Compile,
     // Indicates that the exercise should be compiled as a test harness
     Test,
@@ -37,14 +52,38 @@ pub enum Mode {
     Clippy,
 }
 
-#[derive(Deserialize)]
+impl Display for Mode {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            Mode::Compile => write!(f, "compile"),
+            Mode::Test => write!(f, "test"),
+            Mode::Clippy => write!(f, "clippy"),
+        }
+    }
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(deny_unknown_fields)]
 pub struct ExerciseList {
     pub exercises: Vec<Exercise>,
+    // Named groups of exercises, see `category::Categories`
+    #[serde(default)]
+    pub categories: Categories,
+    // Glob patterns of further catalogues to merge into this one
+    #[serde(default)]
+    pub include: Vec<String>,
+    // The prefix of the exercise names of an included catalogue,
+    // defaulting to its file name
+    pub namespace: Option<String>,
+    // Commands run around builds and runs, only allowed in info.toml itself
+    #[serde(default)]
+    pub hooks: Hooks,
 }
 
 // A representation of a kiga exercise.
 // This is deserialized from the accompanying info.toml file
-#[derive(Deserialize, Debug)]
+#[derive(Deserialize, Debug, Default)]
+#[serde(deny_unknown_fields)]
 pub struct Exercise {
     // Name of the exercise
     pub name: String,
@@ -52,22 +91,66 @@ pub struct Exercise {
     pub path: PathBuf,
     // The mode of the exercise (Test, Compile, or Clippy)
     pub mode: Mode,
-    // The hint text associated with the exercise 
+    // The hint text associated with the exercise
+    #[serde(default)]
     pub hint: String,
+    // Hints revealed one at a time, from a nudge to almost the answer.
+    // Used instead of `hint` when set
+    #[serde(default)]
+    pub hints: Vec<String>,
+    // A markdown file holding the hint, rendered for the terminal. Without
+    // `hint` or `hints`, the `.md` file of the same name next to the
+    // exercise is used, e.g. `intro.md` for `intro.rs`, or else a `HINT.md`
+    // in its directory
+    #[serde(default)]
+    pub hint_file: Option<PathBuf>,
+    // Free-form labels used to select exercises
+    #[serde(default)]
+    pub tags: Vec<String>,
+    // The names of the exercises that should be done before this one
+    #[serde(default)]
+    pub requires: Vec<String>,
+    // The rules deciding whether the exercise is done, checked in order.
+    // When empty, the exercise is done once the `I AM NOT DONE` marker is gone
+    #[serde(default)]
+    pub criteria: Vec<Criterion>,
+    // A pristine copy of the exercise that `reset` restores. Without one,
+    // the version committed to git is used
+    #[serde(default)]
+    pub template: Option<PathBuf>,
+    // The intended answer, shown by `kiga solution` and checked by
+    // `kiga verify --solutions`
+    #[serde(default)]
+    pub solution: Option<PathBuf>,
+    // The last computed state, valid as long as the source file is unchanged
+    #[serde(skip)]
+    state_cache: Mutex<Option<(FileStamp, State)>>,
+    // The compiler errors of the last check, or None if it built
+    #[serde(skip)]
+    build_errors: Mutex<Option<String>>,
 }
 
 // An enum to track of the state of an Exercise.
 // An Exercise can be either Done or Pending
-#[derive(PartialEq, Debug)]
+#[derive(PartialEq, Debug, Clone)]
 pub enum State {
     // The state of the exercise once it's been completed
     Done,
     // The state of the exercise while it's not completed yet
-    Pending(Vec<ContextLine>),
+    Pending(Unmet),
+}
+
+// Why a pending exercise isn't done yet
+#[derive(PartialEq, Debug, Clone)]
+pub struct Unmet {
+    // The first completion criterion that isn't satisfied
+    pub criterion: Criterion,
+    // The source lines around the `I AM NOT DONE` marker, for marker criteria
+    pub context: Vec<ContextLine>,
 }
 
 // The context information of a pending exercise
-#[derive(PartialEq, Debug)]
+#[derive(PartialEq, Debug, Clone)]
 pub struct ContextLine {
     // The source code that is still pending completion
     pub line: String,
@@ -99,8 +182,55 @@ pub struct ExerciseOutput {
     pub stderr: String,
 }
 
+impl ExerciseOutput {
+    // The output of a build or run stopped by a newer change
+    fn interrupted(error: io::Error) -> Self {
+        ExerciseOutput {
+            stdout: String::new(),
+            stderr: error.to_string(),
+        }
+    }
+}
+
 struct FileHandle;
 
+// Identifies a version of a source file by its modification time and size
+type FileStamp = (Option<SystemTime>, u64);
+
+// Lazily builds an exercise at most once while its criteria are checked
+struct Probe<'a> {
+    exercise: &'a Exercise,
+    compiled: Option<bool>,
+    stdout: Option<Option<String>>,
+}
+
+impl<'a> Probe<'a> {
+    fn compiles(&mut self) -> bool {
+        let exercise = self.exercise;
+        *self
+            .compiled
+            .get_or_insert_with(|| exercise.compile().is_ok())
+    }
+
+    // The standard output of a successful run, or None if it failed
+    fn stdout(&mut self) -> Option<&str> {
+        let exercise = self.exercise;
+        let compiled = &mut self.compiled;
+        self.stdout
+            .get_or_insert_with(|| match exercise.compile() {
+                Ok(compilation) => {
+                    *compiled = Some(true);
+                    compilation.run().ok().map(|output| output.stdout)
+                }
+                Err(_) => {
+                    *compiled = Some(false);
+                    None
+                }
+            })
+            .as_deref()
+    }
+}
+
 impl Drop for FileHandle {
     fn drop(&mut self) {
         clean();
@@ -108,32 +238,39 @@ impl Drop for FileHandle {
 }
 
 impl Exercise {
-    pub fn compile(&self) -> Result<CompiledExercise, ExerciseOutput> {
+    // Build the exercise. No hooks run here, so looking at the state of an
+    // exercise has no side effects; attempts build through `hooks::compile`
+    pub fn compile(&self) -> Result<CompiledExercise<'_>, ExerciseOutput> {
         let cmd = match self.mode {
-            Mode::Compile => Command::new("rustc")
-                .args([self.path.to_str().unwrap(), "-o", &temp_file()])
-                .args(RUSTC_COLOR_ARGS)
-                .args(RUSTC_EDITION_ARGS)
-                .args(RUSTC_NO_DEBUG_ARGS)
-                .output(),
-            Mode::Test => Command::new("rustc")
-                .args(["--test", self.path.to_str().unwrap(), "-o", &temp_file()])
-                .args(RUSTC_COLOR_ARGS)
-                .args(RUSTC_EDITION_ARGS)
-                .args(RUSTC_NO_DEBUG_ARGS)
-                .output(),
+            Mode::Compile => job::output(
+                Command::new("rustc")
+                    .args([self.path.to_str().unwrap(), "-o", &temp_file()])
+                    .args(RUSTC_COLOR_ARGS)
+                    .args(RUSTC_EDITION_ARGS)
+                    .args(RUSTC_NO_DEBUG_ARGS),
+            ),
+            Mode::Test => job::output(
+                Command::new("rustc")
+                    .args(["--test", self.path.to_str().unwrap(), "-o", &temp_file()])
+                    .args(RUSTC_COLOR_ARGS)
+                    .args(RUSTC_EDITION_ARGS)
+                    .args(RUSTC_NO_DEBUG_ARGS),
+            ),
             Mode::Clippy => {
+                // Namespaced names aren't valid package names
+                let crate_name = self.name.replace('/', "_");
+                // Relative to the manifest, which lives two directories down
+                let bin_path = Path::new("../..").join(&self.path);
                 let cargo_toml = format!(
                     r#"[package]
-name = "{}"
+name = "{crate_name}"
 version = "0.0.1"
 edition = "2021"
 [[bin]]
-name = "{}"
-path = "{}.rs""#,
-                    self.name, self.name, self.name
+name = "{crate_name}"
+path = {bin_path:?}"#
                 );
-                let cargo_toml_error_msg = if env::var("NO_EMOJI").is_ok() {
+                let cargo_toml_error_msg = if !config::get().emoji() {
                     "Failed to write Clippy Cargo.toml file."
                 } else {
                     "Failed to write 📎 Clippy 📎 Cargo.toml file."
@@ -159,14 +296,22 @@ path = "{}.rs""#,
                     .args(RUSTC_COLOR_ARGS)
                     .output()
                     .expect("Failed to run 'cargo clean'");
-                Command::new("cargo")
-                    .args(["clippy", "--manifest-path", CLIPPY_CARGO_TOML_PATH])
-                    .args(RUSTC_COLOR_ARGS)
-                    .args(["--", "-D", "warnings", "-D", "clippy::float_cmp"])
-                    .output()
+                job::output(
+                    Command::new("cargo")
+                        .args(["clippy", "--manifest-path", CLIPPY_CARGO_TOML_PATH])
+                        .args(RUSTC_COLOR_ARGS)
+                        .args(["--", "-D", "warnings", "-D", "clippy::float_cmp"]),
+                )
             }
-        }
-        .expect("Failed to run 'compile' command.");
+        };
+        let cmd = match cmd {
+            // A newer change in watch mode stopped the build
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => {
+                clean();
+                return Err(ExerciseOutput::interrupted(e));
+            }
+            cmd => cmd.expect("Failed to run 'compile' command."),
+        };
 
         if cmd.status.success() {
             Ok(CompiledExercise {
@@ -187,10 +332,12 @@ path = "{}.rs""#,
             Mode::Test => "--show-output",
             _ => "",
         };
-        let cmd = Command::new(temp_file())
-            .arg(arg)
-            .output()
-            .expect("Failed to run 'run' command");
+        let cmd = match job::output(Command::new(temp_file()).arg(arg)) {
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => {
+                return Err(ExerciseOutput::interrupted(e))
+            }
+            cmd => cmd.expect("Failed to run 'run' command"),
+        };
 
         let output = ExerciseOutput {
             stdout: String::from_utf8_lossy(&cmd.stdout).to_string(),
@@ -204,63 +351,211 @@ path = "{}.rs""#,
         }
     }
 
+    // A copy of the exercise pointing at another file, like its solution
+    pub fn at(&self, path: &Path) -> Exercise {
+        Exercise {
+            name: self.name.clone(),
+            path: path.to_path_buf(),
+            mode: self.mode,
+            criteria: self.criteria.clone(),
+            ..Exercise::default()
+        }
+    }
+
+    // The levels of the hint, of which a single `hint` is the only one
+    pub fn hint_levels(&self) -> Vec<&str> {
+        if self.hints.is_empty() {
+            vec![self.hint.as_str()]
+        } else {
+            self.hints.iter().map(String::as_str).collect()
+        }
+    }
+
+    // The markdown file of the hint, if it has one
+    pub fn hint_file(&self) -> Option<PathBuf> {
+        if let Some(file) = &self.hint_file {
+            return Some(file.clone());
+        }
+        if !self.hint.is_empty() || !self.hints.is_empty() {
+            return None;
+        }
+        let named = self.path.with_extension(HINT_EXTENSION);
+        let shared = self.path.with_file_name(HINT_FILE);
+        [named, shared]
+            .into_iter()
+            .find(|sidecar| sidecar.is_file())
+    }
+
+    // The completion criteria of the exercise, falling back to the marker check
+    pub fn criteria(&self) -> &[Criterion] {
+        if self.criteria.is_empty() {
+            &[Criterion::MarkerAbsent]
+        } else {
+            &self.criteria
+        }
+    }
+
+    // Compute the state of the exercise by checking its criteria in order.
+    // The result is cached until the source file changes, so criteria
+    // that need a build don't rebuild the exercise on every call
     pub fn state(&self) -> State {
-        let mut source_file = File::open(&self.path).unwrap_or_else(|e| {
+        self.cached_state(Probe {
+            exercise: self,
+            compiled: None,
+            stdout: None,
+        })
+    }
+
+    // Compute the state of an exercise that was just built successfully.
+    // `stdout` is the output of the run, if the exercise was also run
+    pub fn state_after_build(&self, stdout: Option<&str>) -> State {
+        self.cached_state(Probe {
+            exercise: self,
+            compiled: Some(true),
+            stdout: stdout.map(|s| Some(s.to_string())),
+        })
+    }
+
+    // The state of the exercise as far as it's known without building it or
+    // running a command: the cached state while the source is unchanged, or
+    // else what criteria reading only the source decide. None when finding
+    // out needs a build, which watch mode leaves to its background checks
+    pub fn known_state(&self) -> Option<State> {
+        if let Some((stamp, state)) = &*self.state_cache.lock().unwrap() {
+            if *stamp == self.stamp() {
+                return Some(state.clone());
+            }
+        }
+        let static_only = self.criteria().iter().all(Criterion::is_static);
+        static_only.then(|| self.state())
+    }
+
+    fn stamp(&self) -> FileStamp {
+        fs::metadata(&self.path)
+            .map(|m| (m.modified().ok(), m.len()))
+            .unwrap_or_default()
+    }
+
+    fn cached_state(&self, probe: Probe) -> State {
+        let stamp = self.stamp();
+        if let Some((cached_stamp, state)) = &*self.state_cache.lock().unwrap() {
+            // A probe that already built the exercise carries fresher facts
+            if *cached_stamp == stamp && probe.compiled.is_none() {
+                return state.clone();
+            }
+        }
+        // The lock isn't held while evaluating, as that may build the
+        // exercise, and others asking for its state shouldn't wait on it
+        let state = self.evaluate(probe);
+        *self.state_cache.lock().unwrap() = Some((stamp, state.clone()));
+        state
+    }
+
+    fn evaluate(&self, mut probe: Probe) -> State {
+        let source = fs::read_to_string(&self.path).unwrap_or_else(|e| {
             panic!(
-                "We were unable to open the exercise file {}! {e}",
+                "We were unable to read the exercise file {}! {e}",
                 self.path.display()
             )
         });
 
-        let source = {
-            let mut s = String::new();
-            source_file.read_to_string(&mut s).unwrap_or_else(|e| {
-                panic!(
-                    "We were unable to read the exercise file {}! {e}",
-                    self.path.display()
-                )
+        for criterion in self.criteria() {
+            let context = match criterion {
+                Criterion::MarkerAbsent => match marker_context(&source) {
+                    Some(context) => context,
+                    None => continue,
+                },
+                Criterion::MarkerPresent if I_AM_DONE.is_match(&source) => continue,
+                Criterion::Compiles if probe.compiles() => continue,
+                Criterion::TestsPass if probe.stdout().is_some() => continue,
+                Criterion::OutputMatches { pattern } => {
+                    let matched = match Regex::new(pattern) {
+                        Ok(re) => probe.stdout().is_some_and(|out| re.is_match(out)),
+                        Err(_) => false,
+                    };
+                    if matched {
+                        continue;
+                    }
+                    Vec::new()
+                }
+                Criterion::Command { command } if self.predicate_holds(command) => continue,
+                _ => Vec::new(),
+            };
+            return State::Pending(Unmet {
+                criterion: criterion.clone(),
+                context,
             });
-            s
-        };
-
-        let re = Regex::new(I_AM_DONE_REGEX).unwrap();
-
-        if !re.is_match(&source) {
-            return State::Done;
         }
 
-        let matched_line_index = source
-            .lines()
-            .enumerate()
-            .find_map(|(i, line)| if re.is_match(line) { Some(i) } else { None })
-            .expect("This should not happen at all");
-
-        let min_line = ((matched_line_index as i32) - (CONTEXT as i32)).max(0) as usize;
-        let max_line = matched_line_index + CONTEXT;
-
-        let context = source
-            .lines()
-            .enumerate()
-            .filter(|&(i, _)| i >= min_line && i <= max_line)
-            .map(|(i, line)| ContextLine {
-                line: line.to_string(),
-                number: i + 1,
-                important: i == matched_line_index,
-            })
-            .collect();
+        State::Done
+    }
 
-        State::Pending(context)
+    // Run a custom predicate command for this exercise
+    fn predicate_holds(&self, command: &[String]) -> bool {
+        let Some((program, args)) = command.split_first() else {
+            return false;
+        };
+        job::output(
+            Command::new(program)
+                .args(args)
+                .env("KIGA_EXERCISE", &self.name)
+                .env("KIGA_PATH", &self.path),
+        )
+        .map(|output| output.status.success())
+        .unwrap_or(false)
     }
 
     // Check that the exercise looks to be solved using self.state()
-    // This is not the best way to check since
-    // the user can just remove the "I AM NOT DONE" string from the file
+    // With the default criterion this only checks for the marker,
+    // so the user can just remove the "I AM NOT DONE" string from the file
     // without actually having solved anything.
-    // The only other way to truly check this would to compile and run
-    // the exercise; which would be both costly and counterintuitive
+    // Exercises that need a real check can list `compiles`, `tests_pass`
+    // or `output_matches` in their criteria, at the cost of a build
     pub fn looks_done(&self) -> bool {
         self.state() == State::Done
     }
+
+    // Whether the exercise is known to be done, without building it
+    pub fn known_done(&self) -> bool {
+        self.known_state() == Some(State::Done)
+    }
+
+    // Keep what the compiler said in a check, for `explain` to read the
+    // error codes from the output the user is looking at
+    pub fn set_build_errors(&self, stderr: Option<String>) {
+        *self.build_errors.lock().unwrap() = stderr;
+    }
+
+    pub fn build_errors(&self) -> Option<String> {
+        self.build_errors.lock().unwrap().clone()
+    }
+}
+
+// The lines around the `I AM NOT DONE` marker, or None if there is no marker
+fn marker_context(source: &str) -> Option<Vec<ContextLine>> {
+    let matched_line_index = source.lines().enumerate().find_map(|(i, line)| {
+        if I_AM_DONE.is_match(line) {
+            Some(i)
+        } else {
+            None
+        }
+    })?;
+
+    let min_line = matched_line_index.saturating_sub(CONTEXT);
+    let max_line = matched_line_index + CONTEXT;
+
+    let context = source
+        .lines()
+        .enumerate()
+        .filter(|&(i, _)| i >= min_line && i <= max_line)
+        .map(|(i, line)| ContextLine {
+            line: line.to_string(),
+            number: i + 1,
+            important: i == matched_line_index,
+        })
+        .collect();
+
+    Some(context)
 }
 
 impl Display for Exercise {
@@ -274,9 +569,33 @@ fn clean() {
     let _ignored = remove_file(temp_file());
 }
 
+// Builders for the exercises of other modules' tests
+#[cfg(test)]
+impl Exercise {
+    // An exercise stored at `exercises/<name>.rs`
+    pub fn named(name: &str) -> Exercise {
+        Exercise {
+            name: name.to_string(),
+            path: PathBuf::from(format!("exercises/{name}.rs")),
+            ..Default::default()
+        }
+    }
+
+    pub fn tagged(mut self, tags: &[&str]) -> Exercise {
+        self.tags = tags.iter().map(|t| t.to_string()).collect();
+        self
+    }
+
+    pub fn requiring(mut self, names: &[&str]) -> Exercise {
+        self.requires = names.iter().map(|n| n.to_string()).collect();
+        self
+    }
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
+    use std::fs::File;
     use std::path::Path;
 
     #[test]
@@ -287,6 +606,7 @@ mod test {
             path: PathBuf::from("tests/fixture/state/pending_exercise.rs"),
             mode: Mode::Compile,
             hint: String::from(""),
+            ..Default::default()
         };
         let compiled = exercise.compile().unwrap();
         drop(compiled);
@@ -305,6 +625,7 @@ mod test {
                     path: PathBuf::from("tests/fixture/state/pending_exercise.rs"),
                     mode: *mode,
                     hint: String::from(""),
+                    ..Default::default()
                 };
                 let _ = exercise.compile().unwrap();
                 assert!(!Path::new(&format!("{}.pdb", temp_file())).exists());
@@ -318,6 +639,7 @@ mod test {
             path: PathBuf::from("tests/fixture/state/pending_exercise.rs"),
             mode: Mode::Compile,
             hint: String::new(),
+            ..Default::default()
         };
 
         let state = exercise.state();
@@ -349,7 +671,13 @@ mod test {
             },
         ];
 
-        assert_eq!(state, State::Pending(expected));
+        assert_eq!(
+            state,
+            State::Pending(Unmet {
+                criterion: Criterion::MarkerAbsent,
+                context: expected,
+            })
+        );
     }
 
     #[test]
@@ -359,9 +687,12 @@ mod test {
             path: PathBuf::from("tests/fixture/state/finished_exercise.rs"),
             mode: Mode::Compile,
             hint: String::new(),
+            ..Default::default()
         };
 
         assert_eq!(exercise.state(), State::Done);
+        // The marker check reads only the source, so it's known without a build
+        assert_eq!(exercise.known_state(), Some(State::Done));
     }
 
     #[test]
@@ -371,8 +702,60 @@ mod test {
             path: PathBuf::from("tests/fixture/success/testSuccess.rs"),
             mode: Mode::Test,
             hint: String::new(),
+            ..Default::default()
         };
         let out = exercise.compile().unwrap().run().unwrap();
         assert!(out.stdout.contains("THIS TEST TOO SHALL PASS"));
     }
+
+    #[test]
+    fn test_first_unmet_criterion() {
+        let exercise = Exercise {
+            name: "finished_exercise".into(),
+            path: PathBuf::from("tests/fixture/state/finished_exercise.rs"),
+            mode: Mode::Compile,
+            criteria: vec![
+                Criterion::MarkerAbsent,
+                Criterion::Compiles,
+                Criterion::OutputMatches {
+                    pattern: "^never printed$".into(),
+                },
+            ],
+            ..Default::default()
+        };
+
+        assert_eq!(
+            exercise.state(),
+            State::Pending(Unmet {
+                criterion: Criterion::OutputMatches {
+                    pattern: "^never printed$".into(),
+                },
+                context: Vec::new(),
+            })
+        );
+    }
+
+    #[test]
+    fn test_output_matches_after_build() {
+        let exercise = Exercise {
+            name: "finished_exercise".into(),
+            path: PathBuf::from("tests/fixture/state/finished_exercise.rs"),
+            mode: Mode::Compile,
+            criteria: vec![Criterion::OutputMatches {
+                pattern: "(?m)^42$".into(),
+            }],
+            ..Default::default()
+        };
+        // Telling needs a build, which `known_state` leaves to the checks
+        assert_eq!(exercise.known_state(), None);
+
+        assert!(matches!(
+            exercise.state_after_build(Some("41\n")),
+            State::Pending(_)
+        ));
+        assert_eq!(exercise.state_after_build(Some("42\n")), State::Done);
+        // The cached state is reused while the file is unchanged
+        assert_eq!(exercise.state(), State::Done);
+        assert!(exercise.known_done());
+    }
 }
@@ -0,0 +1,243 @@
+use crate::exercise::Exercise;
+use clap::ValueEnum;
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap};
+use std::fmt::{self, Display, Formatter};
+
+// A problem with the `requires` entries of the catalogue
+#[derive(Debug, PartialEq)]
+pub enum GraphError {
+    // An exercise requires an exercise that doesn't exist
+    UnknownRequirement {
+        exercise: String,
+        requirement: String,
+    },
+    // Exercises require each other in a loop, listed in requirement order
+    Cycle(Vec<String>),
+}
+
+impl Display for GraphError {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            GraphError::UnknownRequirement {
+                exercise,
+                requirement,
+            } => write!(
+                f,
+                "exercise '{exercise}' requires '{requirement}', which doesn't exist"
+            ),
+            GraphError::Cycle(names) => {
+                write!(f, "exercises require each other in a cycle: ")?;
+                write!(f, "{} -> {}", names.join(" -> "), names[0])
+            }
+        }
+    }
+}
+
+// How `kiga graph` prints the dependency graph
+#[derive(ValueEnum, Copy, Clone, Debug, Default, PartialEq)]
+pub enum GraphFormat {
+    // One line per exercise listing its requirements
+    #[default]
+    Text,
+    // Graphviz DOT, e.g. for `kiga graph --format dot | dot -Tsvg`
+    Dot,
+}
+
+// Order exercises so each one comes after the exercises it requires.
+// Among exercises whose requirements are met, the file order is kept,
+// so a catalogue without `requires` keeps its order unchanged
+pub fn sort(exercises: Vec<Exercise>) -> Result<Vec<Exercise>, Vec<GraphError>> {
+    let order = topological_order(&exercises)?;
+    let mut slots: Vec<Option<Exercise>> = exercises.into_iter().map(Some).collect();
+    Ok(order
+        .into_iter()
+        .map(|i| slots[i].take().expect("each exercise is ordered once"))
+        .collect())
+}
+
+// The indices of the exercises in topological order
+pub fn topological_order(exercises: &[Exercise]) -> Result<Vec<usize>, Vec<GraphError>> {
+    let index: HashMap<&str, usize> = exercises
+        .iter()
+        .enumerate()
+        .map(|(i, e)| (e.name.as_str(), i))
+        .collect();
+
+    let mut errors = Vec::new();
+    let mut dependents = vec![Vec::new(); exercises.len()];
+    let mut missing = vec![0usize; exercises.len()];
+    for (i, exercise) in exercises.iter().enumerate() {
+        for requirement in &exercise.requires {
+            match index.get(requirement.as_str()) {
+                Some(&r) => {
+                    dependents[r].push(i);
+                    missing[i] += 1;
+                }
+                None => errors.push(GraphError::UnknownRequirement {
+                    exercise: exercise.name.clone(),
+                    requirement: requirement.clone(),
+                }),
+            }
+        }
+    }
+    if !errors.is_empty() {
+        return Err(errors);
+    }
+
+    // Kahn's algorithm, always taking the earliest ready exercise
+    let mut ready: BinaryHeap<Reverse<usize>> = (0..exercises.len())
+        .filter(|&i| missing[i] == 0)
+        .map(Reverse)
+        .collect();
+    let mut order = Vec::with_capacity(exercises.len());
+    while let Some(Reverse(i)) = ready.pop() {
+        order.push(i);
+        for &d in &dependents[i] {
+            missing[d] -= 1;
+            if missing[d] == 0 {
+                ready.push(Reverse(d));
+            }
+        }
+    }
+
+    if order.len() == exercises.len() {
+        Ok(order)
+    } else {
+        Err(vec![GraphError::Cycle(find_cycle(
+            exercises, &index, &missing,
+        ))])
+    }
+}
+
+// Walk requirements from an exercise left unordered until a name repeats.
+// Every unordered exercise still misses a requirement that is unordered too,
+// so the walk always runs into a cycle
+fn find_cycle(
+    exercises: &[Exercise],
+    index: &HashMap<&str, usize>,
+    missing: &[usize],
+) -> Vec<String> {
+    let mut path: Vec<usize> = Vec::new();
+    let mut current = missing
+        .iter()
+        .position(|&m| m > 0)
+        .expect("a cycle leaves exercises unordered");
+    while !path.contains(&current) {
+        path.push(current);
+        current = exercises[current]
+            .requires
+            .iter()
+            .map(|r| index[r.as_str()])
+            .find(|&r| missing[r] > 0)
+            .expect("an unordered exercise misses an unordered requirement");
+    }
+    let start = path.iter().position(|&i| i == current).unwrap();
+    // Report the cycle in the direction of "is required by"
+    path[start..]
+        .iter()
+        .rev()
+        .map(|&i| exercises[i].name.clone())
+        .collect()
+}
+
+// Whether every exercise required by `exercise` is known to be done.
+// Watch mode asks this on its main thread, so nothing is built for it
+pub fn requirements_met(exercise: &Exercise, exercises: &[&Exercise]) -> bool {
+    exercise.requires.iter().all(|r| {
+        exercises
+            .iter()
+            .find(|e| &e.name == r)
+            .is_none_or(|e| e.known_done())
+    })
+}
+
+// Render the dependency graph of the exercises
+pub fn render(exercises: &[Exercise], format: GraphFormat) -> String {
+    let mut out = String::new();
+    match format {
+        GraphFormat::Text => {
+            for exercise in exercises {
+                out.push_str(&exercise.name);
+                if !exercise.requires.is_empty() {
+                    out.push_str(" <- ");
+                    out.push_str(&exercise.requires.join(", "));
+                }
+                out.push('\n');
+            }
+        }
+        GraphFormat::Dot => {
+            out.push_str("digraph kiga {\n");
+            for exercise in exercises {
+                out.push_str(&format!("    {:?};\n", exercise.name));
+            }
+            for exercise in exercises {
+                for requirement in &exercise.requires {
+                    out.push_str(&format!("    {requirement:?} -> {:?};\n", exercise.name));
+                }
+            }
+            out.push_str("}\n");
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn names(exercises: &[Exercise]) -> Vec<&str> {
+        exercises.iter().map(|e| e.name.as_str()).collect()
+    }
+
+    #[test]
+    fn test_sort_keeps_file_order_when_possible() {
+        let exercises = vec![
+            Exercise::named("a").requiring(&["c"]),
+            Exercise::named("b"),
+            Exercise::named("c"),
+            Exercise::named("d").requiring(&["a"]),
+        ];
+        assert_eq!(names(&sort(exercises).unwrap()), ["b", "c", "a", "d"]);
+
+        let unchanged = vec![Exercise::named("x"), Exercise::named("y")];
+        assert_eq!(names(&sort(unchanged).unwrap()), ["x", "y"]);
+    }
+
+    #[test]
+    fn test_unknown_requirement() {
+        let exercises = vec![Exercise::named("a").requiring(&["nope"])];
+        assert_eq!(
+            sort(exercises).unwrap_err(),
+            [GraphError::UnknownRequirement {
+                exercise: "a".into(),
+                requirement: "nope".into(),
+            }]
+        );
+    }
+
+    #[test]
+    fn test_cycle() {
+        let exercises = vec![
+            Exercise::named("free"),
+            Exercise::named("a").requiring(&["c"]),
+            Exercise::named("b").requiring(&["a"]),
+            Exercise::named("c").requiring(&["b"]),
+        ];
+        let errors = sort(exercises).unwrap_err();
+        assert_eq!(errors.len(), 1);
+        let GraphError::Cycle(cycle) = &errors[0] else {
+            panic!("expected a cycle, got {:?}", errors[0]);
+        };
+        assert_eq!(cycle.len(), 3);
+        assert!(!cycle.contains(&"free".to_string()));
+    }
+
+    #[test]
+    fn test_render_dot() {
+        let exercises = vec![Exercise::named("a"), Exercise::named("b").requiring(&["a"])];
+        let dot = render(&exercises, GraphFormat::Dot);
+        assert!(dot.starts_with("digraph kiga {\n"));
+        assert!(dot.contains("    \"a\" -> \"b\";\n"));
+    }
+}
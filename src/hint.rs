@@ -0,0 +1,125 @@
+use crate::exercise::Exercise;
+use crate::markdown;
+use crate::progress;
+use std::fs;
+
+// The levels of an exercise's hint, from its markdown file rendered for the
+// terminal, or as written in info.toml
+fn texts(exercise: &Exercise, styled: bool) -> Vec<String> {
+    let Some(file) = exercise.hint_file() else {
+        return exercise
+            .hint_levels()
+            .into_iter()
+            .map(String::from)
+            .collect();
+    };
+    match fs::read_to_string(&file) {
+        Ok(source) => {
+            let levels = markdown::render(&source, styled);
+            if levels.is_empty() {
+                vec![String::new()]
+            } else {
+                levels
+            }
+        }
+        Err(e) => vec![format!("Can't read the hint in {}: {e}", file.display())],
+    }
+}
+
+// One level of a hint, headed with its place when there are several.
+// `level` counts from 1
+fn level(texts: &[String], level: usize) -> String {
+    let level = level.clamp(1, texts.len());
+    let text = &texts[level - 1];
+    if texts.len() == 1 {
+        text.to_string()
+    } else {
+        format!("Hint {level} of {}:\n{text}", texts.len())
+    }
+}
+
+// The first `shown` levels of a hint, one after the other
+pub fn levels(texts: &[String], shown: usize) -> String {
+    (1..=shown.clamp(1, texts.len()))
+        .map(|n| level(texts, n))
+        .collect::<Vec<_>>()
+        .join("\n\n")
+}
+
+// Every level of a hint
+pub fn all(exercise: &Exercise) -> String {
+    let texts = texts(exercise, true);
+    levels(&texts, texts.len())
+}
+
+// The levels of a hint without colors, for the watch mode TUI to keep
+pub fn plain(exercise: &Exercise) -> Vec<String> {
+    texts(exercise, false)
+}
+
+// Reveal the next level of a hint and return it. Once they have all been
+// seen, the last one is shown again
+pub fn next(exercise: &Exercise) -> String {
+    let texts = texts(exercise, true);
+    let seen = progress::reveal_hint(&exercise.name, texts.len());
+    let mut text = level(&texts, seen);
+    if texts.len() > 1 && seen == texts.len() {
+        text.push_str("\n\nThat was the last hint.");
+    }
+    text
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_hint_levels() {
+        let mut single = Exercise::default();
+        single.hint = "use kiga run intro".to_string();
+        assert_eq!(all(&single), "use kiga run intro");
+
+        let mut exercise = Exercise::default();
+        exercise.hint = "ignored".to_string();
+        exercise.hints = vec!["nudge".to_string(), "bigger nudge".to_string()];
+        let texts = texts(&exercise, false);
+        assert_eq!(level(&texts, 2), "Hint 2 of 2:\nbigger nudge");
+        assert_eq!(levels(&texts, 0), "Hint 1 of 2:\nnudge");
+        assert_eq!(
+            levels(&plain(&exercise), 2),
+            "Hint 1 of 2:\nnudge\n\nHint 2 of 2:\nbigger nudge"
+        );
+    }
+
+    #[test]
+    fn test_hint_file_levels() {
+        let dir = std::env::temp_dir().join(format!("kiga_hint_{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(
+            dir.join("intro.md"),
+            "Look at *threads*.\n\n---\n\nUse `join`.\n",
+        )
+        .unwrap();
+        let mut exercise = Exercise::default();
+        exercise.path = dir.join("intro.rs");
+        // Another exercise in the same directory doesn't share it, but
+        // falls back to the directory's `HINT.md`
+        let mut other = Exercise::default();
+        other.path = dir.join("threads.rs");
+        assert_eq!(other.hint_file(), None);
+        fs::write(dir.join("HINT.md"), "Spawn a thread.\n").unwrap();
+        assert_eq!(other.hint_file(), Some(dir.join("HINT.md")));
+        assert_eq!(plain(&other), ["Spawn a thread."]);
+        assert_eq!(exercise.hint_file(), Some(dir.join("intro.md")));
+
+        assert_eq!(plain(&exercise).len(), 2);
+        assert_eq!(
+            levels(&plain(&exercise), 2),
+            "Hint 1 of 2:\nLook at threads.\n\nHint 2 of 2:\nUse join."
+        );
+        // A hint in info.toml wins over the sidecar file
+        exercise.hint = "inline".to_string();
+        assert_eq!(plain(&exercise), ["inline"]);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
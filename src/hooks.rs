@@ -0,0 +1,221 @@
+use crate::config;
+use crate::exercise::{CompiledExercise, Exercise, ExerciseOutput};
+use crate::job;
+use crate::progress::Outcome;
+use serde::Deserialize;
+use std::io::{self, Write};
+use std::process::Command;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::RwLock;
+
+// Commands run at points in the life of an exercise, from the `[hooks]`
+// table of info.toml. The `hooks.*` settings override them one by one
+#[derive(Deserialize, Clone, Debug, Default, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct Hooks {
+    pub pre_compile: Option<String>,
+    pub post_compile: Option<String>,
+    pub post_run: Option<String>,
+    pub on_success: Option<String>,
+    pub on_failure: Option<String>,
+    pub on_all_done: Option<String>,
+}
+
+static HOOKS: RwLock<Hooks> = RwLock::new(Hooks {
+    pre_compile: None,
+    post_compile: None,
+    post_run: None,
+    on_success: None,
+    on_failure: None,
+    on_all_done: None,
+});
+
+// Set while the watch mode TUI owns the terminal, so hooks run without
+// printing over it
+static QUIET: AtomicBool = AtomicBool::new(false);
+
+// Use the hooks of a freshly loaded info.toml
+pub fn init(hooks: Hooks) {
+    *HOOKS.write().unwrap() = hooks;
+}
+
+pub fn set_quiet(quiet: bool) {
+    QUIET.store(quiet, Ordering::SeqCst);
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Hook {
+    // Before an exercise is built
+    PreCompile,
+    // After an exercise was built, with the result `success` or `failure`
+    PostCompile,
+    // After an exercise was run, with the result `success` or `failure`
+    PostRun,
+    // When an attempt at an exercise passed
+    OnSuccess,
+    // When an attempt didn't pass, with the result `failed` or `pending`
+    OnFailure,
+    // When watch mode finds every exercise done
+    OnAllDone,
+}
+
+impl Hook {
+    fn name(self) -> &'static str {
+        match self {
+            Hook::PreCompile => "pre_compile",
+            Hook::PostCompile => "post_compile",
+            Hook::PostRun => "post_run",
+            Hook::OnSuccess => "on_success",
+            Hook::OnFailure => "on_failure",
+            Hook::OnAllDone => "on_all_done",
+        }
+    }
+
+    // The command to run, from the settings or else from info.toml
+    fn command(self) -> Option<String> {
+        if let Some(command) = config::get().hook(self.name()) {
+            return Some(command.to_string());
+        }
+        let hooks = HOOKS.read().unwrap();
+        let command = match self {
+            Hook::PreCompile => &hooks.pre_compile,
+            Hook::PostCompile => &hooks.post_compile,
+            Hook::PostRun => &hooks.post_run,
+            Hook::OnSuccess => &hooks.on_success,
+            Hook::OnFailure => &hooks.on_failure,
+            Hook::OnAllDone => &hooks.on_all_done,
+        };
+        command.clone()
+    }
+}
+
+// Run a hook, if one is set, through the shell. It finds KIGA_HOOK and,
+// where they apply, KIGA_EXERCISE, KIGA_PATH, KIGA_MODE and KIGA_RESULT in
+// its environment. A failing hook is reported but stops nothing
+pub fn run(hook: Hook, exercise: Option<&Exercise>, result: Option<&str>) {
+    let Some(command) = hook.command().filter(|c| !c.trim().is_empty()) else {
+        return;
+    };
+    let mut shell = shell(&command);
+    shell.env("KIGA_HOOK", hook.name());
+    if let Some(exercise) = exercise {
+        shell
+            .env("KIGA_EXERCISE", &exercise.name)
+            .env("KIGA_PATH", &exercise.path)
+            .env("KIGA_MODE", exercise.mode.to_string());
+    }
+    if let Some(result) = result {
+        shell.env("KIGA_RESULT", result);
+    }
+    // Inside a watch mode job the hook is killed with the job
+    let output = job::output(&mut shell);
+    if QUIET.load(Ordering::SeqCst) {
+        return;
+    }
+    match output {
+        Ok(output) => {
+            let _ = io::stdout().write_all(&output.stdout);
+            let _ = io::stderr().write_all(&output.stderr);
+            if !output.status.success() {
+                println!("The {} hook failed with {}", hook.name(), output.status);
+            }
+        }
+        Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+        Err(e) => println!("The {} hook `{command}` couldn't run: {e}", hook.name()),
+    }
+}
+
+// Run the on_success or on_failure hook for a finished attempt
+pub fn finished(exercise: &Exercise, outcome: Outcome) {
+    match outcome {
+        Outcome::Passed => run(Hook::OnSuccess, Some(exercise), Some("passed")),
+        Outcome::Pending => run(Hook::OnFailure, Some(exercise), Some("pending")),
+        Outcome::Failed => run(Hook::OnFailure, Some(exercise), Some("failed")),
+    }
+}
+
+// Build an exercise the user is attempting, between the pre_compile and
+// post_compile hooks
+pub fn compile(exercise: &Exercise) -> Result<CompiledExercise<'_>, ExerciseOutput> {
+    run(Hook::PreCompile, Some(exercise), None);
+    let compiled = exercise.compile();
+    // A build stopped by a newer change didn't really happen
+    if !job::cancelled() {
+        run(Hook::PostCompile, Some(exercise), Some(result(&compiled)));
+    }
+    compiled
+}
+
+// Run an exercise built by `compile`, then the post_run hook
+pub fn run_compiled(
+    exercise: &Exercise,
+    compilation: &CompiledExercise,
+) -> Result<ExerciseOutput, ExerciseOutput> {
+    let output = compilation.run();
+    if !job::cancelled() {
+        run(Hook::PostRun, Some(exercise), Some(result(&output)));
+    }
+    output
+}
+
+// `success` or `failure`, for the results of post_compile and post_run
+fn result<T, E>(result: &Result<T, E>) -> &'static str {
+    if result.is_ok() {
+        "success"
+    } else {
+        "failure"
+    }
+}
+
+#[cfg(unix)]
+fn shell(command: &str) -> Command {
+    let mut shell = Command::new("sh");
+    shell.args(["-c", command]);
+    shell
+}
+
+#[cfg(windows)]
+fn shell(command: &str) -> Command {
+    let mut shell = Command::new("cmd");
+    shell.args(["/C", command]);
+    shell
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::criteria::Criterion;
+
+    #[test]
+    fn test_hooks_from_info_toml() {
+        let hooks: Hooks =
+            toml::from_str("post_run = \"echo ran\"\non_all_done = \"true\"").unwrap();
+        assert_eq!(hooks.post_run.as_deref(), Some("echo ran"));
+        assert_eq!(hooks.pre_compile, None);
+        assert!(toml::from_str::<Hooks>("on_start = \"echo\"").is_err());
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_only_attempts_run_hooks() {
+        let marker = std::env::temp_dir().join(format!("kiga_hook_{}", std::process::id()));
+        init(Hooks {
+            pre_compile: Some(format!("touch {}", marker.display())),
+            ..Hooks::default()
+        });
+        let mut exercise = Exercise::default();
+        exercise.name = "finished_exercise".to_string();
+        exercise.path = "tests/fixture/state/finished_exercise.rs".into();
+        exercise.criteria = vec![Criterion::Compiles];
+
+        // Looking at the state builds the exercise, quietly
+        assert!(exercise.looks_done());
+        assert!(!marker.exists());
+        let compilation = compile(&exercise).unwrap();
+        assert!(run_compiled(&exercise, &compilation).is_ok());
+        assert!(marker.exists());
+
+        init(Hooks::default());
+        std::fs::remove_file(&marker).unwrap();
+    }
+}
@@ -0,0 +1,191 @@
+use std::cell::RefCell;
+use std::io::{self, Read};
+use std::path::PathBuf;
+use std::process::{Command, Output, Stdio};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, LazyLock};
+use std::thread::{self, Scope, ScopedJoinHandle};
+use std::time::Duration;
+
+// How often a running child process is checked for cancellation
+const POLL_INTERVAL: Duration = Duration::from_millis(20);
+
+// Set when Ctrl-C is pressed while interrupts are caught
+static INTERRUPTED: LazyLock<Arc<AtomicBool>> = LazyLock::new(Arc::default);
+
+thread_local! {
+    // The token of the job running on this thread, if any
+    static CURRENT: RefCell<Option<CancelToken>> = const { RefCell::new(None) };
+}
+
+// Shared between a job and whoever started it, to stop it early
+#[derive(Clone, Debug, Default)]
+pub struct CancelToken(Arc<AtomicBool>);
+
+impl CancelToken {
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::SeqCst);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::SeqCst)
+    }
+}
+
+// Whether the job running on this thread was cancelled. Always false
+// outside of a job, e.g. for `kiga run`
+pub fn cancelled() -> bool {
+    CURRENT.with(|current| current.borrow().as_ref().is_some_and(|t| t.is_cancelled()))
+}
+
+// Catch Ctrl-C instead of dying from it, so watch mode can cancel its jobs,
+// which removes their temporary binaries, and restore the terminal
+pub fn catch_interrupts() -> io::Result<()> {
+    signal_hook::flag::register(signal_hook::consts::SIGINT, Arc::clone(&INTERRUPTED))?;
+    Ok(())
+}
+
+// Whether Ctrl-C was pressed since the last call
+pub fn interrupted() -> bool {
+    INTERRUPTED.swap(false, Ordering::SeqCst)
+}
+
+// Run `work` with `token` as the current token of this thread
+fn with_token<T>(token: CancelToken, work: impl FnOnce() -> T) -> T {
+    CURRENT.with(|current| *current.borrow_mut() = Some(token));
+    let result = work();
+    CURRENT.with(|current| *current.borrow_mut() = None);
+    result
+}
+
+// Like `Command::output`, but inside a job the child is killed as soon as
+// the job is cancelled, which is reported as an `Interrupted` error
+pub fn output(command: &mut Command) -> io::Result<Output> {
+    let Some(token) = CURRENT.with(|current| current.borrow().clone()) else {
+        return command.output();
+    };
+    let mut child = command
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()?;
+    // Drain the pipes while waiting, so a chatty child can't fill them and block
+    let stdout = read_to_end(child.stdout.take());
+    let stderr = read_to_end(child.stderr.take());
+    let status = loop {
+        if token.is_cancelled() {
+            let _ = child.kill();
+            let _ = child.wait();
+            return Err(io::Error::new(
+                io::ErrorKind::Interrupted,
+                "cancelled by a newer change",
+            ));
+        }
+        if let Some(status) = child.try_wait()? {
+            break status;
+        }
+        thread::sleep(POLL_INTERVAL);
+    };
+    Ok(Output {
+        status,
+        stdout: stdout.join().unwrap_or_default(),
+        stderr: stderr.join().unwrap_or_default(),
+    })
+}
+
+fn read_to_end(pipe: Option<impl Read + Send + 'static>) -> thread::JoinHandle<Vec<u8>> {
+    thread::spawn(move || {
+        let mut buffer = Vec::new();
+        if let Some(mut pipe) = pipe {
+            let _ = pipe.read_to_end(&mut buffer);
+        }
+        buffer
+    })
+}
+
+// Work on some exercises running in the background of watch mode
+pub struct Job<'scope, T> {
+    // The exercises the job works on
+    targets: Vec<PathBuf>,
+    token: CancelToken,
+    handle: ScopedJoinHandle<'scope, T>,
+}
+
+impl<'scope, T: Send + 'scope> Job<'scope, T> {
+    pub fn spawn<'env>(
+        scope: &'scope Scope<'scope, 'env>,
+        targets: Vec<PathBuf>,
+        work: impl FnOnce() -> T + Send + 'scope,
+    ) -> Self {
+        let token = CancelToken::default();
+        let job_token = token.clone();
+        let handle = scope.spawn(move || with_token(job_token, work));
+        Job {
+            targets,
+            token,
+            handle,
+        }
+    }
+
+    // Whether the job works on any of these exercises
+    pub fn overlaps(&self, targets: &[PathBuf]) -> bool {
+        self.targets.iter().any(|t| targets.contains(t))
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.handle.is_finished()
+    }
+
+    // Wait for the job to finish, passing on its panic if it had one
+    pub fn join(self) -> T {
+        self.handle
+            .join()
+            .unwrap_or_else(|panic| std::panic::resume_unwind(panic))
+    }
+
+    // Kill whatever the job is running and wait for it to stop
+    pub fn cancel(self) {
+        self.token.cancel();
+        let _ = self.handle.join();
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::time::Instant;
+
+    #[test]
+    #[cfg(unix)]
+    fn test_cancel_kills_the_child() {
+        let started = Instant::now();
+        thread::scope(|scope| {
+            let job = Job::spawn(scope, vec![PathBuf::from("a.rs")], || {
+                let result = output(Command::new("sleep").arg("10"));
+                (result.map_err(|e| e.kind()), cancelled())
+            });
+            assert!(job.overlaps(&[PathBuf::from("a.rs")]));
+            thread::sleep(Duration::from_millis(100));
+            job.token.cancel();
+            let (result, cancelled) = job.join();
+            assert_eq!(result.unwrap_err(), io::ErrorKind::Interrupted);
+            assert!(cancelled);
+        });
+        assert!(started.elapsed() < Duration::from_secs(5));
+        assert!(!cancelled());
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_output_in_a_job() {
+        thread::scope(|scope| {
+            let job = Job::spawn(scope, Vec::new(), || {
+                output(Command::new("sh").args(["-c", "echo out; echo err >&2"]))
+            });
+            let output = job.join().unwrap();
+            assert!(output.status.success());
+            assert_eq!(output.stdout, b"out\n");
+            assert_eq!(output.stderr, b"err\n");
+        });
+    }
+}
@@ -0,0 +1,255 @@
+use crate::category::{self, Categories, Selection};
+use crate::exercise::{Exercise, Mode};
+use crate::progress::{self, History, Progress};
+use crate::root::normalize;
+use clap::ValueEnum;
+use glob::Pattern;
+use regex::Regex;
+use std::fs;
+use std::io::{self, Write};
+use std::path::PathBuf;
+
+// The key the exercise list is sorted by
+#[derive(ValueEnum, Copy, Clone, Debug, PartialEq)]
+pub enum SortKey {
+    // Alphabetically by name
+    Name,
+    // Alphabetically by path
+    Path,
+    // Most recently run first, never run last
+    LastRun,
+    // Pending exercises first
+    Status,
+}
+
+// A column of the exercise table
+#[derive(ValueEnum, Copy, Clone, Debug, PartialEq)]
+pub enum Column {
+    Name,
+    Path,
+    Status,
+    Mode,
+    // The outcome of the last recorded attempt
+    LastResult,
+    // How long the last recorded attempt took
+    Duration,
+    // The size of the exercise file
+    Size,
+}
+
+pub const DEFAULT_COLUMNS: &[Column] = &[Column::Name, Column::Path, Column::Status];
+
+// Which exercises `kiga list` shows and how
+pub struct ListOptions {
+    // Show only the paths of the exercises
+    pub paths: bool,
+    // Show only the names of the exercises
+    pub names: bool,
+    // Comma separated substrings to match names and paths
+    pub filter: Option<String>,
+    pub regex: Option<Regex>,
+    pub glob: Option<Pattern>,
+    pub modes: Vec<Mode>,
+    pub selection: Selection,
+    pub dir: Option<PathBuf>,
+    pub unsolved: bool,
+    pub solved: bool,
+    pub sort: Option<SortKey>,
+    pub columns: Vec<Column>,
+}
+
+// An exercise together with everything the list may show about it
+struct Row<'a> {
+    exercise: &'a Exercise,
+    done: bool,
+    history: Option<&'a History>,
+}
+
+impl ListOptions {
+    fn matches(&self, row: &Row, categories: &Categories) -> bool {
+        let e = row.exercise;
+        let fname = format!("{}", e.path.display());
+
+        let filters = self.filter.clone().unwrap_or_default().to_lowercase();
+        let filter_cond = self.filter.is_none()
+            || filters
+                .split(',')
+                .filter(|f| !f.trim().is_empty())
+                .any(|f| e.name.contains(f) || fname.contains(f));
+        let regex_cond = self
+            .regex
+            .as_ref()
+            .is_none_or(|re| re.is_match(&e.name) || re.is_match(&fname));
+        let glob_cond = self
+            .glob
+            .as_ref()
+            .is_none_or(|g| g.matches(&e.name) || g.matches_path(&e.path));
+        let mode_cond = self.modes.is_empty() || self.modes.contains(&e.mode);
+        let selection_cond = self.selection.matches(e, categories);
+        let dir_cond = self
+            .dir
+            .as_ref()
+            .is_none_or(|dir| normalize(&e.path).starts_with(normalize(dir)));
+        let solve_cond = (row.done && self.solved)
+            || (!row.done && self.unsolved)
+            || (!self.solved && !self.unsolved);
+
+        filter_cond
+            && regex_cond
+            && glob_cond
+            && mode_cond
+            && selection_cond
+            && dir_cond
+            && solve_cond
+    }
+}
+
+// Print the exercises selected by the options, followed by the overall progress.
+// When the catalogue defines categories, the table is grouped by category
+pub fn list(exercises: &[Exercise], categories: &Categories, options: &ListOptions) {
+    let progress = Progress::load();
+    let mut rows: Vec<Row> = exercises
+        .iter()
+        .map(|exercise| Row {
+            exercise,
+            done: exercise.looks_done(),
+            history: progress.history(&exercise.name),
+        })
+        .collect();
+    let exercises_done = rows.iter().filter(|row| row.done).count();
+
+    rows.retain(|row| options.matches(row, categories));
+    match options.sort {
+        Some(SortKey::Name) => rows.sort_by(|a, b| a.exercise.name.cmp(&b.exercise.name)),
+        Some(SortKey::Path) => rows.sort_by(|a, b| a.exercise.path.cmp(&b.exercise.path)),
+        Some(SortKey::LastRun) => {
+            rows.sort_by_key(|row| std::cmp::Reverse(last_run(row)));
+        }
+        Some(SortKey::Status) => rows.sort_by_key(|row| row.done),
+        None => {}
+    }
+
+    if options.paths {
+        rows.iter()
+            .for_each(|row| write_line(&format!("{}", row.exercise.path.display())));
+    } else if options.names {
+        rows.iter().for_each(|row| write_line(&row.exercise.name));
+    } else {
+        let columns = if options.columns.is_empty() {
+            DEFAULT_COLUMNS
+        } else {
+            &options.columns
+        };
+        print_table(&rows, columns, categories);
+    }
+
+    let percentage_progress = exercises_done as f32 / exercises.len() as f32 * 100.0;
+    println!(
+        "Progress: You completed {} / {} exercises ({:.1} %).",
+        exercises_done,
+        exercises.len(),
+        percentage_progress
+    );
+}
+
+fn print_table(rows: &[Row], columns: &[Column], categories: &Categories) {
+    let header: Vec<String> = columns.iter().map(|c| heading(*c).to_string()).collect();
+    let cells: Vec<Vec<String>> = rows
+        .iter()
+        .map(|row| columns.iter().map(|c| cell(row, *c)).collect())
+        .collect();
+
+    let widths: Vec<usize> = (0..columns.len())
+        .map(|i| {
+            cells
+                .iter()
+                .map(|line| line[i].chars().count())
+                .chain([header[i].len()])
+                .max()
+                .unwrap_or(0)
+        })
+        .collect();
+
+    let format_line = |line: &[String]| {
+        let formatted: Vec<String> = line
+            .iter()
+            .zip(&widths)
+            .map(|(text, width)| format!("{text:<width$}"))
+            .collect();
+        formatted.join("  ").trim_end().to_string()
+    };
+
+    if categories.is_empty() {
+        write_line(&format_line(&header));
+        cells.iter().for_each(|line| write_line(&format_line(line)));
+        return;
+    }
+
+    let lines: Vec<(&Row, &Vec<String>)> = rows.iter().zip(&cells).collect();
+    for (i, (category, members)) in
+        category::group(&lines, |(row, _)| &row.exercise.name, categories)
+            .into_iter()
+            .enumerate()
+    {
+        let done = members.iter().filter(|(row, _)| row.done).count();
+        if i > 0 {
+            write_line("");
+        }
+        write_line(&format!(
+            "[{category}] {done} / {} done ({:.1} %)",
+            members.len(),
+            done as f32 / members.len() as f32 * 100.0
+        ));
+        write_line(&format_line(&header));
+        members
+            .iter()
+            .for_each(|(_, line)| write_line(&format_line(line)));
+    }
+}
+
+fn heading(column: Column) -> &'static str {
+    match column {
+        Column::Name => "Name",
+        Column::Path => "Path",
+        Column::Status => "Status",
+        Column::Mode => "Mode",
+        Column::LastResult => "Last result",
+        Column::Duration => "Duration",
+        Column::Size => "Size",
+    }
+}
+
+fn cell(row: &Row, column: Column) -> String {
+    let last = row.history.and_then(|h| h.recent.last());
+    match column {
+        Column::Name => row.exercise.name.clone(),
+        Column::Path => format!("{}", row.exercise.path.display()),
+        Column::Status => if row.done { "Done" } else { "Pending" }.to_string(),
+        Column::Mode => row.exercise.mode.to_string(),
+        Column::LastResult => last.map_or("-".to_string(), |a| a.outcome.to_string()),
+        Column::Duration => last.map_or("-".to_string(), |a| {
+            progress::format_duration(a.duration_ms)
+        }),
+        Column::Size => {
+            fs::metadata(&row.exercise.path).map_or("-".to_string(), |m| format!("{} B", m.len()))
+        }
+    }
+}
+
+fn last_run(row: &Row) -> Option<u64> {
+    row.history.and_then(|h| h.recent.last()).map(|a| a.at)
+}
+
+// Somehow using println! leads to the binary panicking
+// when its output is piped.
+// So, we're handling a Broken Pipe error and exiting with 0 anyway
+fn write_line(line: &str) {
+    let stdout = io::stdout();
+    let mut handle = stdout.lock();
+    writeln!(handle, "{line}").unwrap_or_else(|e| {
+        match e.kind() {
+            io::ErrorKind::BrokenPipe => std::process::exit(0),
+            _ => std::process::exit(1),
+        };
+    });
+}
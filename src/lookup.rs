@@ -0,0 +1,246 @@
+use crate::exercise::Exercise;
+use crate::progress::{Outcome, Progress};
+use crate::root::{normalize, Root};
+use std::path::Path;
+
+// Names that stand for an exercise picked from your progress
+pub const ALIASES: &[&str] = &["next", "prev", "current", "last-failed"];
+// How many "did you mean" suggestions are shown at most
+const MAX_SUGGESTIONS: usize = 5;
+
+// What a name given on the command line refers to
+#[derive(Debug)]
+pub enum Lookup<'a> {
+    Found(&'a Exercise),
+    // Several exercises match equally well
+    Ambiguous(Vec<&'a Exercise>),
+    // Nothing matches; the closest names, best first
+    Unknown(Vec<&'a Exercise>),
+    // An alias that points at no exercise right now, and why
+    Unavailable(String),
+}
+
+// Find an exercise by alias, exact name, path (relative to where kiga was
+// started), unqualified name, or name prefix, in that order.
+// If nothing matches, suggest the names closest to the query
+pub fn lookup<'a>(
+    query: &str,
+    exercises: &'a [Exercise],
+    root: &Root,
+    progress: &Progress,
+) -> Lookup<'a> {
+    if ALIASES.contains(&query) {
+        return alias(query, exercises, progress);
+    }
+    if let Some(exercise) = exercises.iter().find(|e| e.name == query) {
+        return Lookup::Found(exercise);
+    }
+
+    let path = normalize(&root.resolve(Path::new(query)));
+    if let Some(exercise) = exercises.iter().find(|e| normalize(&e.path) == path) {
+        return Lookup::Found(exercise);
+    }
+
+    // Unqualified names match `<namespace>/<name>` exercises
+    let unqualified = matching(exercises, |e| short_name(e) == query);
+    if !unqualified.is_empty() {
+        return found_or_ambiguous(unqualified);
+    }
+
+    let query_lower = query.to_lowercase();
+    let prefixed = matching(exercises, |e| {
+        e.name.to_lowercase().starts_with(&query_lower)
+            || short_name(e).to_lowercase().starts_with(&query_lower)
+    });
+    if !prefixed.is_empty() {
+        return found_or_ambiguous(prefixed);
+    }
+
+    Lookup::Unknown(suggestions(&query_lower, exercises))
+}
+
+fn alias<'a>(query: &str, exercises: &'a [Exercise], progress: &Progress) -> Lookup<'a> {
+    let next = || exercises.iter().position(|e| !e.looks_done());
+    let found = match query {
+        "next" => next().map(|i| &exercises[i]).ok_or(
+            "🎉 Congratulations! You have done all the exercises!\n🔚 There are no more exercises to do next!",
+        ),
+        "prev" => match next() {
+            Some(0) => Err("There is no exercise before the first one!"),
+            Some(i) => Ok(&exercises[i - 1]),
+            None => exercises.last().ok_or("There are no exercises!"),
+        },
+        "current" => latest(exercises, progress, |_| true)
+            .ok_or("You haven't attempted any exercise yet!"),
+        "last-failed" => latest(exercises, progress, |outcome| outcome == Outcome::Failed)
+            .ok_or("No exercise has failed yet!"),
+        _ => unreachable!("'{query}' is not an alias"),
+    };
+    match found {
+        Ok(exercise) => Lookup::Found(exercise),
+        Err(reason) => Lookup::Unavailable(reason.to_string()),
+    }
+}
+
+// The exercise with the most recent attempt whose outcome is accepted
+fn latest<'a>(
+    exercises: &'a [Exercise],
+    progress: &Progress,
+    accept: impl Fn(Outcome) -> bool,
+) -> Option<&'a Exercise> {
+    exercises
+        .iter()
+        .filter_map(|e| {
+            let history = progress.history(&e.name)?;
+            let at = history
+                .recent
+                .iter()
+                .filter(|a| accept(a.outcome))
+                .map(|a| a.at)
+                .max()?;
+            Some((at, e))
+        })
+        .max_by_key(|(at, _)| *at)
+        .map(|(_, e)| e)
+}
+
+fn matching(exercises: &[Exercise], pred: impl Fn(&Exercise) -> bool) -> Vec<&Exercise> {
+    exercises.iter().filter(|e| pred(e)).collect()
+}
+
+fn found_or_ambiguous(candidates: Vec<&Exercise>) -> Lookup<'_> {
+    match candidates[..] {
+        [exercise] => Lookup::Found(exercise),
+        _ => Lookup::Ambiguous(candidates),
+    }
+}
+
+// The name without its namespace
+fn short_name(exercise: &Exercise) -> &str {
+    exercise.name.rsplit('/').next().unwrap_or(&exercise.name)
+}
+
+// The exercises whose name is within a few edits of the query, closest first
+fn suggestions<'a>(query: &str, exercises: &'a [Exercise]) -> Vec<&'a Exercise> {
+    let threshold = (query.chars().count() / 3).max(2);
+    let mut ranked: Vec<(usize, &Exercise)> = exercises
+        .iter()
+        .map(|e| {
+            let full = levenshtein(query, &e.name.to_lowercase());
+            let short = levenshtein(query, &short_name(e).to_lowercase());
+            (full.min(short), e)
+        })
+        .filter(|(distance, _)| *distance <= threshold)
+        .collect();
+    // The sort is stable, so equally close names keep the recommended order
+    ranked.sort_by_key(|(distance, _)| *distance);
+    ranked
+        .into_iter()
+        .take(MAX_SUGGESTIONS)
+        .map(|(_, e)| e)
+        .collect()
+}
+
+// The number of single character insertions, deletions and substitutions
+// needed to turn `a` into `b`
+fn levenshtein(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let above = row[j + 1];
+            row[j + 1] = if ca == *cb {
+                diagonal
+            } else {
+                1 + diagonal.min(above).min(row[j])
+            };
+            diagonal = above;
+        }
+    }
+    row[b.len()]
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::progress::{Attempt, Origin};
+    use std::path::PathBuf;
+
+    fn root() -> Root {
+        Root {
+            dir: PathBuf::from("/kiga"),
+            manifest: PathBuf::from("info.toml"),
+            invoked_from: PathBuf::from("/kiga/exercises"),
+        }
+    }
+
+    fn found(lookup: Lookup<'_>) -> &str {
+        match lookup {
+            Lookup::Found(exercise) => &exercise.name,
+            other => panic!("expected a single exercise, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn test_lookup_by_path_prefix_and_namespace() {
+        // Paths in info.toml may start with `./`
+        let mut variables2 = Exercise::named("variables2");
+        variables2.path = PathBuf::from("./exercises/variables2.rs");
+        let exercises = [
+            Exercise::named("variables1"),
+            variables2,
+            Exercise::named("extra/threads"),
+        ];
+        let progress = Progress::default();
+        let lookup = |query| super::lookup(query, &exercises, &root(), &progress);
+
+        assert_eq!(found(lookup("variables2.rs")), "variables2");
+        assert_eq!(found(lookup("threads")), "extra/threads");
+        assert_eq!(found(lookup("thr")), "extra/threads");
+        assert!(matches!(lookup("var"), Lookup::Ambiguous(c) if c.len() == 2));
+    }
+
+    #[test]
+    fn test_suggestions_are_ranked() {
+        let exercises = [
+            Exercise::named("strings2"),
+            Exercise::named("threads"),
+            Exercise::named("strings"),
+        ];
+        let progress = Progress::default();
+        let Lookup::Unknown(suggestions) = lookup("strngs", &exercises, &root(), &progress) else {
+            panic!("'strngs' shouldn't match");
+        };
+        let names: Vec<&str> = suggestions.iter().map(|e| e.name.as_str()).collect();
+        assert_eq!(names, ["strings", "strings2"]);
+        assert_eq!(levenshtein("kitten", "sitting"), 3);
+    }
+
+    #[test]
+    fn test_progress_aliases() {
+        let exercises = [
+            Exercise::named("a"),
+            Exercise::named("b"),
+            Exercise::named("c"),
+        ];
+        let mut progress = Progress::default();
+        let attempt = |at, outcome| Attempt {
+            at,
+            origin: Origin::Run,
+            outcome,
+            duration_ms: 0,
+        };
+        progress.record("a", attempt(1, Outcome::Failed));
+        progress.record("b", attempt(2, Outcome::Pending));
+        let lookup = |query| super::lookup(query, &exercises, &root(), &progress);
+
+        assert_eq!(found(lookup("current")), "b");
+        assert_eq!(found(lookup("last-failed")), "a");
+        assert!(matches!(
+            super::lookup("current", &exercises, &root(), &Progress::default()),
+            Lookup::Unavailable(_)
+        ));
+    }
+}
@@ -1,70 +1,88 @@
 // 导入本项目中的 exercise 模块中的 Exercise 和 ExerciseList 类型，并使它们在当前作用域中可用
-use crate::exercise::{Exercise, ExerciseList};
+use crate::category::{Categories, Selection};
+use crate::completions::Shell;
+use crate::exercise::{Exercise, ExerciseList, Mode, State};
+use crate::graph::GraphFormat;
+use crate::hooks::Hook;
+use crate::job::Job;
+use crate::list::{Column, ListOptions, SortKey};
+use crate::lookup::Lookup;
+use crate::progress::{Origin, Outcome, Progress};
 use crate::project::RustAnalyzerProject;
-use crate::run::{reset, run};
+use crate::root::{Root, ROOT_ENV};
+use crate::run::run;
+use crate::shell::{Commands, ShellCommand};
 
-use crate::verify::verify;
+use crate::verify::{verify, verify_solutions};
+use crate::watcher::{Change, FileWatcher};
 // clap 是 Rust 中的一个库，用于解析命令行参数。它提供了一个简单易用的 API，可以帮助开发者快速定义和解析命令行参数，并生成帮助文档和版本信息等。
-use clap::{Parser, Subcommand};
+use clap::{CommandFactory, Parser, Subcommand};
+use clap_complete::{ArgValueCandidates, CompleteEnv};
 // console crate 是一个用于在控制台中输出彩色文本和表情符号的 Rust 库。Emoji 类型是 console crate 中的一个结构体，用于表示一个 Unicode 表情符号
 use console::Emoji;
-// notify crate 是一个用于监视文件系统事件的 Rust 库。DebouncedEvent 枚举类型是 notify crate 中的一个枚举类型，用于表示文件系统事件
-use notify::DebouncedEvent;
-// RecommendedWatcher 类型是 notify crate 中的一个结构体，实现了 Watcher trait，并提供了一个推荐的监视器实现
-// RecursiveMode 类型是 notify crate 中的一个枚举类型，用于指定监视器是否应该递归地监视子目录
-// Watcher 类型是 notify crate 中的一个 trait，用于定义监视文件系统事件的行为
-use notify::{RecommendedWatcher, RecursiveMode, Watcher};
-// std::ffi::OsStr 是 Rust 标准库中的一个类型，用于表示操作系统原生字符串。
-use std::ffi::OsStr;
-// std::fs 模块提供了一些与文件系统交互相关的功能，包括文件和目录的创建、删除、重命名、复制、读取和写入等操作。
-// std::fs 模块中的函数和方法通常返回 std::io::Result 类型，用于表示操作是否成功
-use std::fs;
+use glob::Pattern;
+use regex::Regex;
+// std::collections 模块提供了常用的集合类型，VecDeque 是一个双端队列
+use std::collections::VecDeque;
 // std::io 模块提供了与输入输出相关的功能，包括文件读写、标准输入输出、网络通信等
-// std::io::prelude 模块中包含了一些常用的 trait，例如 Read、Write、BufRead 等，这些 trait 可以帮助开发者更方便地进行输入输出操作
-use std::io::{self, prelude::*};
+use std::io::{self, IsTerminal};
 // std::path 模块提供了一些与文件系统路径相关的功能，包括路径的构建、解析、拼接、比较等。
-use std::path::Path;
+use std::path::{Path, PathBuf};
 // 用于导入 std::process 模块中的 Command 和 Stdio 类型，并使它们在当前作用域中可用
 // std::process 模块提供了与进程相关的功能，包括创建新进程、与子进程进行交互等
 // Command 类型是 std::process 模块中的一个结构体，用于表示要执行的命令及其参数。Command 结构体有一些方法，用于设置命令及其参数
 // Stdio 类型是 std::process 模块中的一个枚举类型，用于表示进程的标准输入、输出和错误输出。
 // Stdio 枚举类型有三个成员,inherit、piped 和 null，分别表示继承父进程的标准输入、输出和错误输出、创建一个管道和丢弃输入、输出和错误输出定向到空设备
 use std::process::{Command, Stdio};
-// std::sync::atomic 模块提供了原子类型的支持，包括原子布尔值、原子整数等。
-// Ordering 类型是 std::sync::atomic 模块中的一个枚举类型，用于表示原子操作的内存顺序。
-use std::sync::atomic::{AtomicBool, Ordering};
-// std::sync::mpsc 模块提供了多个生产者、单个消费者（MSPC）通道的支持，用于在多个线程之间传递消息。
-// channel 函数是 std::sync::mpsc 模块中的一个函数，用于创建一个新的 MSPC 通道。
-// RecvTimeoutError 类型是 std::sync::mpsc 模块中的一个枚举类型，用于表示从 MSPC 通道接收消息时可能出现的超时错误。
-use std::sync::mpsc::{channel, RecvTimeoutError};
-// std::sync 模块提供了多种并发原语的支持，包括原子类型、互斥锁、条件变量等。
-// Arc 类型是 std::sync 模块中的一个结构体，用于表示原子引用计数。
-// Mutex 类型是 std::sync 模块中的一个结构体，用于表示互斥锁。
-use std::sync::{Arc, Mutex};
 // std::thread 模块提供了线程的支持，包括创建线程、等待线程完成、线程同步等。
 use std::thread;
 // std::time 模块提供了时间相关的支持，包括计时器、时间间隔等。Duration 类型是 std::time 模块中的一个结构体，用于表示时间间隔。
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
 // #[macro_use] 是一个属性宏，用于在 Rust 中导入宏并使其在当前作用域中可用。
 // 定义在 ui 模块中的宏可以在当前模块中使用，而不需要重新定义
 #[macro_use]
 mod ui;
+mod alert;
+mod catalogue;
+mod category;
+mod completions;
+mod config;
+mod criteria;
+mod docs;
 mod exercise;
+mod graph;
+mod hint;
+mod hooks;
+mod job;
+mod list;
+mod lookup;
+mod markdown;
+mod progress;
 mod project;
+mod root;
 mod run;
+mod shell;
+mod snapshot;
+mod solution;
+mod tui;
 mod verify;
+mod watcher;
 
-/// 编译器为这个结构体自动生成命令行参数解析器。
+// 编译器为这个结构体自动生成命令行参数解析器。
 #[derive(Parser)]
-/// 诉编译器这个结构体是一个命令行程序，并且它有一个名为 version 的子命令
-#[command(version)]
+// 诉编译器这个结构体是一个命令行程序，并且它有一个名为 version 的子命令
+#[command(version, about, disable_help_subcommand = true)]
 struct Args {
     /// Show outputs from the test exercises
-    /// #[arg(long)] 属性，表示它是一个长选项。
+    // #[arg(long)] 属性，表示它是一个长选项。
     #[arg(long)]
     nocapture: bool,
-    /// #[command(subcommand)] 属性，表示它是一个子命令。
+    /// Path to the info.toml to use, instead of searching the current
+    /// directory and its parents. Can also be set with KIGA_ROOT
+    #[arg(long, global = true)]
+    manifest: Option<PathBuf>,
+    // #[command(subcommand)] 属性，表示它是一个子命令。
     #[command(subcommand)]
     command: Option<Subcommands>,
 }
@@ -72,26 +90,80 @@ struct Args {
 #[derive(Subcommand)]
 enum Subcommands {
     /// Verify all exercises according to the recommended order
-    Verify,
+    Verify {
+        #[command(flatten)]
+        selection: Selection,
+        /// Check that the stored solutions compile and pass, instead of the exercises
+        #[arg(long)]
+        solutions: bool,
+    },
     /// Rerun `verify` when files were edited
     Watch {
         /// Show hints on success
         #[arg(long)]
         success_hints: bool,
+        /// Print to the terminal line by line instead of the full-screen UI
+        #[arg(long)]
+        no_tui: bool,
+        /// Only rerun the exercise whose file changed, and show its output
+        #[arg(long)]
+        focus: bool,
+        /// Poll for changes every MS milliseconds (default 1000) instead of
+        /// using file system events, e.g. on network filesystems
+        #[arg(long, value_name = "MS", num_args = 0..=1, require_equals = true, default_missing_value = "1000")]
+        poll: Option<u64>,
+        #[command(flatten)]
+        selection: Selection,
+        /// Only watch these exercises, rerunning them on every change
+        #[arg(add = ArgValueCandidates::new(completions::exercise_names))]
+        names: Vec<String>,
     },
     /// Run/Test a single exercise
     Run {
         /// The name of the exercise
+        #[arg(add = ArgValueCandidates::new(completions::exercise_names))]
         name: String,
     },
-    /// Reset a single exercise using "git stash -- filename"
+    /// Reset a single exercise to its template or committed version, saving a snapshot first
     Reset {
         /// The name of the exercise
+        #[arg(add = ArgValueCandidates::new(completions::exercise_names))]
+        name: String,
+    },
+    /// Bring back the version of an exercise from before its last reset
+    Undo {
+        /// The name of the exercise
+        #[arg(add = ArgValueCandidates::new(completions::exercise_names))]
+        name: String,
+    },
+    /// List the saved snapshots of an exercise
+    Snapshots {
+        /// The name of the exercise
+        #[arg(add = ArgValueCandidates::new(completions::exercise_names))]
         name: String,
     },
-    /// Return a hint for the given exercise
+    /// Return a hint for the given exercise, one more level each time
     Hint {
         /// The name of the exercise
+        #[arg(add = ArgValueCandidates::new(completions::exercise_names))]
+        name: String,
+        /// Show every level of the hint at once
+        #[arg(short, long)]
+        all: bool,
+    },
+    /// Print the stored solution of an exercise, after asking
+    Solution {
+        /// The name of the exercise
+        #[arg(add = ArgValueCandidates::new(completions::exercise_names))]
+        name: String,
+        /// Show it without asking first
+        #[arg(short, long)]
+        yes: bool,
+    },
+    /// Show how an exercise differs from its stored solution
+    Diff {
+        /// The name of the exercise
+        #[arg(add = ArgValueCandidates::new(completions::exercise_names))]
         name: String,
     },
     /// List the exercises available in kiga
@@ -112,30 +184,180 @@ enum Subcommands {
         /// Display only exercises that have been solved
         #[arg(short, long)]
         solved: bool,
+        /// Display only exercises whose name or path matches a regex
+        #[arg(long, value_parser = Regex::new)]
+        regex: Option<Regex>,
+        /// Display only exercises whose name or path matches a glob pattern
+        #[arg(long, value_parser = Pattern::new)]
+        glob: Option<Pattern>,
+        /// Display only exercises of the given mode, may be repeated
+        #[arg(long = "mode", value_enum)]
+        modes: Vec<Mode>,
+        #[command(flatten)]
+        selection: Selection,
+        /// Display only exercises under the given directory
+        #[arg(long)]
+        dir: Option<PathBuf>,
+        /// Sort the exercises instead of keeping the recommended order
+        #[arg(long, value_enum)]
+        sort: Option<SortKey>,
+        /// Comma separated columns to display
+        #[arg(long, value_enum, value_delimiter = ',')]
+        columns: Vec<Column>,
     },
     /// Enable rust-analyzer for exercises
     Lsp,
+    /// Show the recorded attempts of a single exercise
+    History {
+        /// The name of the exercise
+        #[arg(add = ArgValueCandidates::new(completions::exercise_names))]
+        name: String,
+    },
+    /// Show statistics about your recorded progress
+    Stats,
+    /// Validate info.toml and report every problem found
+    CheckConfig,
+    /// Print the graph of exercise prerequisites
+    Graph {
+        /// The output format
+        #[arg(long, value_enum, default_value_t)]
+        format: GraphFormat,
+    },
+    /// Print a completion script for your shell, e.g.
+    /// `source <(kiga completions bash)`
+    Completions {
+        /// The shell to complete in
+        #[arg(value_enum)]
+        shell: Shell,
+    },
+    /// Print the manual page, or write one page per command to a directory
+    Manpage {
+        /// Write kiga.1, kiga-run.1, ... to this directory instead
+        #[arg(long)]
+        dir: Option<PathBuf>,
+    },
+    /// Print the help of kiga or of one of its commands
+    Help {
+        /// Print a markdown reference of every command instead
+        #[arg(long)]
+        markdown: bool,
+        /// The command to describe, e.g. `config set`
+        command: Vec<String>,
+    },
+    /// Read or change kiga's settings
+    Config {
+        #[command(subcommand)]
+        action: ConfigAction,
+    },
+}
+
+#[derive(Subcommand)]
+enum ConfigAction {
+    /// Print the effective value of a setting and where it comes from
+    Get {
+        /// The setting, e.g. `watch.debounce_ms`
+        key: String,
+    },
+    /// Change a setting in the project's .kiga.toml
+    Set {
+        /// The setting, e.g. `watch.debounce_ms`
+        key: String,
+        /// The new value
+        value: String,
+        /// Change the user-level config file instead
+        #[arg(long)]
+        global: bool,
+    },
+    /// Print every setting with its effective value and source
+    List,
 }
 
 fn main() {
+    // 如果是补全脚本在请求补全，则打印候选项并退出程序
+    CompleteEnv::with_factory(Args::command)
+        .var(completions::COMPLETE_ENV)
+        .complete();
+
     // 解析命令行参数
     let args = Args::parse();
 
+    // 如果提供的子命令是 Manpage 或 Help，则打印文档并退出程序
+    if let Some(Subcommands::Manpage { .. } | Subcommands::Help { .. }) = &args.command {
+        print_docs(args.command.as_ref().unwrap());
+    }
+
+    // 如果提供的子命令是 Completions，则打印补全脚本并退出程序
+    if let Some(Subcommands::Completions { shell }) = args.command {
+        match completions::write(shell, &mut io::stdout()) {
+            // 输出被管道截断时（例如 `| head`）正常退出
+            Err(e) if e.kind() != io::ErrorKind::BrokenPipe => {
+                eprintln!("Failed to write the completion script: {e}");
+                std::process::exit(1);
+            }
+            _ => std::process::exit(0),
+        }
+    }
+
     // 如果没有提供子命令，则打印欢迎信息
     if args.command.is_none() {
         println!("\n{WELCOME}\n");
     }
 
-    // 如果当前目录下没有 info.toml 文件，则打印错误信息并退出程序
-    if !Path::new("info.toml").exists() {
-        println!(
-            "{} must be run from the kiga directory",
-            std::env::current_exe().unwrap().to_str().unwrap()
-        );
-        println!("Try `cd kiga/`!");
+    // 从当前目录向上查找 info.toml 所在的项目根目录，并切换到该目录
+    let root = Root::discover(args.manifest.as_deref()).unwrap_or_else(|e| {
+        println!("{e}");
+        println!("Try `cd kiga/`, `--manifest path/to/info.toml` or setting {ROOT_ENV}!");
+        std::process::exit(1);
+    });
+    if let Err(e) = root.enter() {
+        println!("Failed to enter {}: {e}", root.dir.display());
         std::process::exit(1);
     }
 
+    // 按 用户配置、项目配置、环境变量、命令行参数 的顺序加载配置
+    let mut cli = Vec::new();
+    if args.nocapture {
+        cli.push(("verbose", toml::Value::Boolean(true), "--nocapture"));
+    }
+    if let Some(Subcommands::Watch {
+        success_hints,
+        poll,
+        ..
+    }) = &args.command
+    {
+        if *success_hints {
+            cli.push((
+                "success_hints",
+                toml::Value::Boolean(true),
+                "--success-hints",
+            ));
+        }
+        if let Some(ms) = poll {
+            cli.push(("watch.poll_ms", toml::Value::Integer(*ms as i64), "--poll"));
+        }
+    }
+    // 先初始化配置，warn! 等宏会读取它
+    config::init(config::Config::load(&cli));
+    let settings = config::get();
+    for warning in &settings.warnings {
+        warn!("{}", warning);
+    }
+    // 显式设置 colors 时强制开启或关闭，否则保留 console 根据终端自动判断的结果
+    if let Some(colors) = settings.colors() {
+        console::set_colors_enabled(colors);
+        console::set_colors_enabled_stderr(colors);
+    }
+    if let Some(toolchain) = settings.toolchain() {
+        // 子进程 (rustc, cargo, clippy) 会通过 rustup 使用这个工具链
+        std::env::set_var("RUSTUP_TOOLCHAIN", toolchain);
+    }
+
+    // 如果提供的子命令是 Config，则读取或修改配置并退出程序
+    if let Some(Subcommands::Config { action }) = &args.command {
+        config_command(action);
+        std::process::exit(0);
+    }
+
     // 如果当前目录下没有 exercises 目录，则打印错误信息并退出程序
     if !rustc_exists() {
         println!("We cannot find `rustc`.");
@@ -144,12 +366,40 @@ fn main() {
         std::process::exit(1);
     }
 
-    // 从 info.toml 文件中读取练习列表
-    let toml_str = &fs::read_to_string("info.toml").unwrap();
-    // 将练习列表解析为 ExerciseList 类型
-    let exercises = toml::from_str::<ExerciseList>(toml_str).unwrap().exercises;
+    // 从 info.toml 文件中读取并校验练习列表
+    let manifest = root.manifest.as_path();
+    let report = catalogue::load(manifest);
+    // 如果提供的子命令是 CheckConfig，则打印所有诊断信息并退出程序
+    if let Some(Subcommands::CheckConfig) = args.command {
+        let diagnostics = match &report {
+            Ok((_, warnings)) => warnings,
+            Err(diagnostics) => diagnostics,
+        };
+        catalogue::print_report(manifest, diagnostics);
+        std::process::exit(if report.is_ok() { 0 } else { 1 });
+    }
+    // 如果练习列表有错误，则打印错误信息并退出程序
+    let ExerciseList {
+        exercises,
+        categories,
+        include,
+        hooks,
+        ..
+    } = match report {
+        Ok((list, _)) => list,
+        Err(diagnostics) => {
+            diagnostics
+                .iter()
+                .filter(|d| d.is_error())
+                .for_each(|d| println!("{d}\n"));
+            println!("Run `kiga check-config` for a full report.");
+            std::process::exit(1);
+        }
+    };
+    // info.toml 中的钩子命令在编译、运行和完成练习时执行
+    hooks::init(hooks.clone());
     // 如果没有提供子命令，则打印练习列表并退出程序,verbose 为 true 表示打印练习列表
-    let verbose = args.nocapture;
+    let verbose = config::get().verbose();
     let command = args.command.unwrap_or_else(|| {
         println!("{DEFAULT_OUT}\n");
         std::process::exit(0);
@@ -164,99 +414,174 @@ fn main() {
             filter,
             unsolved,
             solved,
+            regex,
+            glob,
+            modes,
+            selection,
+            dir,
+            sort,
+            columns,
         } => {
-            //
-            if !paths && !names {
-                println!("{:<17}\t{:<46}\t{:<7}", "Name", "Path", "Status");
-            }
-            // 统计已完成的练习数量
-            let mut exercises_done: u16 = 0;
-            // 将 filter 转换为小写字母
-            let filters = filter.clone().unwrap_or_default().to_lowercase();
-            // 遍历练习列表中的每一个练习
-            exercises.iter().for_each(|e| {
-                // 将练习的路径转换为字符串
-                let fname = format!("{}", e.path.display());
-                // 如果练习的名称或路径中包含 filter 中的字符串，则将 filter_cond 设置为 true，否则设置为 false
-                let filter_cond = filters
-                    .split(',')
-                    .filter(|f| !f.trim().is_empty())
-                    .any(|f| e.name.contains(f) || fname.contains(f));
-                // 如果练习已完成，则将 status 设置为 Done，否则设置为 Pending
-                let status = if e.looks_done() {
-                    exercises_done += 1;
-                    "Done"
-                } else {
-                    "Pending"
-                };
-                // 如果练习已完成，则将 solved 设置为 true，否则设置为 false
-                let solve_cond = {
-                    (e.looks_done() && solved)
-                        || (!e.looks_done() && unsolved)
-                        || (!solved && !unsolved)
-                };
-                // 如果练习已完成且 filter_cond 为 true，则打印练习的名称、路径和状态
-                if solve_cond && (filter_cond || filter.is_none()) {
-                    let line = if paths {
-                        format!("{fname}\n")
-                    } else if names {
-                        format!("{}\n", e.name)
-                    } else {
-                        format!("{:<17}\t{fname:<46}\t{status:<7}\n", e.name)
-                    };
-                    // Somehow using println! leads to the binary panicking
-                    // when its output is piped.
-                    // So, we're handling a Broken Pipe error and exiting with 0 anyway
-                    let stdout = std::io::stdout();
-                    {
-                        // 临时获取 stdout 的锁
-                        let mut handle = stdout.lock();
-                        // 将 line 写入 stdout
-                        handle.write_all(line.as_bytes()).unwrap_or_else(|e| {
-                            match e.kind() {
-                                std::io::ErrorKind::BrokenPipe => std::process::exit(0),
-                                _ => std::process::exit(1),
-                            };
-                        });
-                    }
-                }
-            });
-            // 打印练习完成的百分比
-            let percentage_progress = exercises_done as f32 / exercises.len() as f32 * 100.0;
-            println!(
-                "Progress: You completed {} / {} exercises ({:.1} %).",
-                exercises_done,
-                exercises.len(),
-                percentage_progress
-            );
+            select(&selection, &exercises, &categories);
+            let options = ListOptions {
+                paths,
+                names,
+                filter,
+                regex,
+                glob,
+                modes,
+                selection,
+                dir: dir.map(|dir| root.resolve(&dir)),
+                unsolved,
+                solved,
+                sort,
+                columns,
+            };
+            list::list(&exercises, &categories, &options);
             std::process::exit(0);
         }
 
         // 如果提供的子命令是 Run，则运行指定的练习
         Subcommands::Run { name } => {
-            let exercise = find_exercise(&name, &exercises);
+            let exercise = find_exercise(&name, &exercises, &root);
 
-            run(exercise, verbose).unwrap_or_else(|_| std::process::exit(1));
+            let started = Instant::now();
+            let result = run(exercise, verbose);
+            let outcome = match result {
+                Ok(()) if exercise.looks_done() => Outcome::Passed,
+                Ok(()) => Outcome::Pending,
+                Err(()) => Outcome::Failed,
+            };
+            progress::record(&exercise.name, Origin::Run, outcome, started.elapsed());
+            hooks::finished(exercise, outcome);
+            result.unwrap_or_else(|_| std::process::exit(1));
         }
 
         // 如果提供的子命令是 Reset，则重置指定的练习
         Subcommands::Reset { name } => {
-            let exercise = find_exercise(&name, &exercises);
+            let exercise = find_exercise(&name, &exercises, &root);
 
-            reset(exercise).unwrap_or_else(|_| std::process::exit(1));
+            match snapshot::reset(exercise) {
+                Ok(snapshot) => println!("{}", snapshot::reset_message(exercise, &snapshot)),
+                Err(message) => {
+                    warn!("{}", message);
+                    std::process::exit(1);
+                }
+            }
+        }
+
+        // 如果提供的子命令是 Undo，则恢复练习最近一次重置之前的版本
+        Subcommands::Undo { name } => {
+            let exercise = find_exercise(&name, &exercises, &root);
+
+            match snapshot::undo(exercise) {
+                Ok(snapshot) => println!(
+                    "Restored {exercise} from the snapshot taken {}",
+                    progress::format_timestamp(snapshot.taken_ms / 1_000)
+                ),
+                Err(message) => {
+                    warn!("{}", message);
+                    std::process::exit(1);
+                }
+            }
+        }
+
+        // 如果提供的子命令是 Snapshots，则列出练习保存的快照，从旧到新
+        Subcommands::Snapshots { name } => {
+            let exercise = find_exercise(&name, &exercises, &root);
+
+            let snapshots = snapshot::list(exercise);
+            if snapshots.is_empty() {
+                println!("There are no snapshots of {exercise}");
+            }
+            for snapshot in snapshots {
+                println!("{}", snapshot.describe());
+            }
         }
 
         // 如果提供的子命令是 Hint，则打印指定练习的提示
-        Subcommands::Hint { name } => {
-            let exercise = find_exercise(&name, &exercises);
+        // 多级提示每次显示下一级，--all 一次显示全部
+        Subcommands::Hint { name, all } => {
+            let exercise = find_exercise(&name, &exercises, &root);
+
+            if all {
+                println!("{}", hint::all(exercise));
+            } else {
+                println!("{}", hint::next(exercise));
+            }
+        }
+
+        // 如果提供的子命令是 Solution，则在确认后打印练习的参考答案
+        Subcommands::Solution { name, yes } => {
+            let exercise = find_exercise(&name, &exercises, &root);
+
+            solution::print(exercise, yes).unwrap_or_else(|message| {
+                warn!("{}", message);
+                std::process::exit(1);
+            });
+        }
+
+        // 如果提供的子命令是 Diff，则显示练习文件与参考答案之间的差异
+        Subcommands::Diff { name } => {
+            let exercise = find_exercise(&name, &exercises, &root);
 
-            println!("{}", exercise.hint);
+            solution::diff(exercise).unwrap_or_else(|message| {
+                warn!("{}", message);
+                std::process::exit(1);
+            });
+        }
+
+        // 如果提供了 --solutions，则验证参考答案本身能否通过
+        Subcommands::Verify {
+            selection,
+            solutions: true,
+        } => {
+            let selected = select(&selection, &exercises, &categories);
+            if verify_solutions(selected.iter().copied()) > 0 {
+                std::process::exit(1);
+            }
         }
 
         // 如果提供的子命令是 Verify，则验证所有练习
-        Subcommands::Verify => {
-            verify(&exercises, (0, exercises.len()), verbose, false)
-                .unwrap_or_else(|_| std::process::exit(1));
+        Subcommands::Verify { selection, .. } => {
+            let selected = select(&selection, &exercises, &categories);
+            let result = verify(
+                selected.iter().copied(),
+                (0, selected.len()),
+                verbose,
+                false,
+                Origin::Verify,
+            );
+            category::print_progress(&selected, &categories);
+            result.unwrap_or_else(|_| std::process::exit(1));
+        }
+
+        // 如果提供的子命令是 History，则打印指定练习的历史记录
+        Subcommands::History { name } => {
+            let exercise = find_exercise(&name, &exercises, &root);
+
+            match Progress::load().history(&exercise.name) {
+                Some(history) => progress::print_history(&exercise.name, history),
+                None => println!("No attempts recorded for '{}' yet.", exercise.name),
+            }
+        }
+
+        // 如果提供的子命令是 Stats，则打印进度统计信息
+        Subcommands::Stats => {
+            progress::print_stats(&Progress::load(), exercises.len());
+        }
+
+        Subcommands::CheckConfig
+        | Subcommands::Config { .. }
+        | Subcommands::Completions { .. }
+        | Subcommands::Manpage { .. }
+        | Subcommands::Help { .. } => {
+            unreachable!("handled before loading the exercises")
+        }
+
+        // 如果提供的子命令是 Graph，则打印练习之间的依赖关系图
+        Subcommands::Graph { format } => {
+            print!("{}", graph::render(&exercises, format));
         }
 
         // 如果提供的子命令是 Lsp，则生成 rust-project.json 文件
@@ -280,118 +605,185 @@ fn main() {
         }
 
         // 如果提供的子命令是 Watch，则启动监视器
-        Subcommands::Watch { success_hints } => match watch(&exercises, verbose, success_hints) {
-            Err(e) => {
-                println!(
-                    "Error: Could not watch your progress. Error message was {:?}.",
-                    e
-                );
-                println!("Most likely you've run out of disk space or your 'inotify limit' has been reached.");
-                std::process::exit(1);
+        Subcommands::Watch {
+            selection,
+            no_tui,
+            focus,
+            names,
+            ..
+        } => {
+            let follow = if !names.is_empty() {
+                Follow::Pinned
+            } else if focus {
+                Follow::Changed
+            } else {
+                Follow::Catalogue
+            };
+            let list = ExerciseList {
+                exercises,
+                categories,
+                include,
+                namespace: None,
+                hooks,
+            };
+            // 终端支持时使用全屏界面，否则（例如输出被重定向）退回到逐行模式
+            let use_tui = !no_tui && io::stdout().is_terminal() && io::stdin().is_terminal();
+            let status = watch_project(
+                &root,
+                list,
+                &selection,
+                &names,
+                follow,
+                use_tui,
+                verbose,
+                config::get().success_hints(),
+            );
+            match status {
+                Err(e) => {
+                    println!(
+                        "Error: Could not watch your progress. Error message was {:?}.",
+                        e
+                    );
+                    println!("Most likely you've run out of disk space or the exercises directory can't be read.");
+                    std::process::exit(1);
+                }
+                Ok(WatchStatus::Finished) => {
+                    println!(
+                        "{emoji} All exercises completed! {emoji}",
+                        emoji = Emoji("🎉", "★")
+                    );
+                    println!("\n{FENISH_LINE}\n");
+                    hooks::run(Hook::OnAllDone, None, None);
+                }
+                Ok(WatchStatus::Unfinished | WatchStatus::Reload(_)) => {
+                    println!("We hope you're enjoying learning about Rust!");
+                    println!("If you want to continue working on the exercises at a later point, you can simply run `kiga watch` again");
+                }
             }
-            Ok(WatchStatus::Finished) => {
-                println!(
-                    "{emoji} All exercises completed! {emoji}",
-                    emoji = Emoji("🎉", "★")
-                );
-                println!("\n{FENISH_LINE}\n");
+        }
+    }
+}
+
+// print_check 函数用于检查单个练习并打印它最新的输出，供 focus 模式使用，
+// verbose 为 true 时也打印测试的输出
+fn print_check(exercise: &Exercise, verbose: bool) -> Outcome {
+    let (outcome, output) = verify::check(exercise, verbose);
+    println!("{output}");
+    match outcome {
+        Outcome::Passed => success!("{} is done!", exercise),
+        Outcome::Pending => warn!("{} isn't done yet", exercise),
+        Outcome::Failed => warn!("{} failed", exercise),
+    }
+    outcome
+}
+
+// print_docs 函数用于打印手册页或帮助信息，然后退出程序
+fn print_docs(command: &Subcommands) -> ! {
+    let mut cmd = Args::command();
+    cmd.build();
+    let result = match command {
+        Subcommands::Manpage { dir: Some(dir) } => docs::write_manpages(&cmd, dir),
+        Subcommands::Manpage { dir: None } => docs::write_manpage(&cmd, &mut io::stdout()),
+        Subcommands::Help { markdown, command } => {
+            // 沿着子命令路径查找要描述的命令
+            let mut target = &mut cmd;
+            for name in command {
+                target = target.find_subcommand_mut(name).unwrap_or_else(|| {
+                    println!("No command named '{name}'!");
+                    std::process::exit(1)
+                });
             }
-            Ok(WatchStatus::Unfinished) => {
-                println!("We hope you're enjoying learning about Rust!");
-                println!("If you want to continue working on the exercises at a later point, you can simply run `kiga watch` again");
+            if *markdown {
+                print!("{}", docs::markdown(target));
+                Ok(())
+            } else {
+                target.print_long_help()
             }
-        },
+        }
+        _ => unreachable!("only documentation commands are printed"),
+    };
+    match result {
+        // 输出被管道截断时（例如 `| head`）正常退出
+        Err(e) if e.kind() != io::ErrorKind::BrokenPipe => {
+            eprintln!("Failed to write the documentation: {e}");
+            std::process::exit(1)
+        }
+        _ => std::process::exit(0),
     }
 }
 
-// spawn_watch_shell 函数用于启动一个新的线程，用于监听用户输入的命令
-fn spawn_watch_shell(
-    // failed_exercise_hint 是一个 Arc<Mutex<Option<String>>> 类型的变量，用于存储当前练习的提示
-    failed_exercise_hint: &Arc<Mutex<Option<String>>>,
-    // should_quit 是一个 Arc<AtomicBool> 类型的变量，用于表示是否退出程序
-    should_quit: Arc<AtomicBool>,
-) {
-    // 将 failed_exercise_hint 和 should_quit 移动到新线程中
-    let failed_exercise_hint = Arc::clone(failed_exercise_hint);
-    println!("Welcome to watch mode! You can type 'help' to get an overview of the commands you can use here.");
-    // 启动一个新的线程，用于监听用户输入的命令
-    thread::spawn(move || loop {
-        // 创建一个新的字符串变量，用于存储用户输入的命令
-        let mut input = String::new();
-        // 从标准输入中读取用户输入的命令
-        match io::stdin().read_line(&mut input) {
-            // 如果读取成功，则执行相应的命令
-            Ok(_) => {
-                // 去掉命令中的空白字符
-                let input = input.trim();
-                // 如果命令是 hint，则打印当前练习的提示
-                if input == "hint" {
-                    if let Some(hint) = &*failed_exercise_hint.lock().unwrap() {
-                        println!("{hint}");
-                    }
-                    // 如果命令是 clear，则清空终端
-                } else if input == "clear" {
-                    println!("\x1B[2J\x1B[1;1H");
-                    // 如果命令是 quit，则退出程序
-                } else if input.eq("quit") {
-                    // 将 should_quit 设置为 true
-                    should_quit.store(true, Ordering::SeqCst);
-                    println!("Bye!");
-                    // 如果命令是 help，则打印帮助信息
-                } else if input.eq("help") {
-                    println!("Commands available to you in watch mode:");
-                    println!("  hint   - prints the current exercise's hint");
-                    println!("  clear  - clears the screen");
-                    println!("  quit   - quits watch mode");
-                    println!("  !<cmd> - executes a command, like `!rustc --explain E0381`");
-                    println!("  help   - displays this help message");
-                    println!();
-                    println!("Watch mode automatically re-evaluates the current exercise");
-                    println!("when you edit a file's contents.")
-                    // 如果命令以 ! 开头，则执行相应的命令
-                } else if let Some(cmd) = input.strip_prefix('!') {
-                    // 将命令按空白字符分割为多个部分
-                    let parts: Vec<&str> = cmd.split_whitespace().collect();
-                    // 如果命令为空，则打印错误信息
-                    if parts.is_empty() {
-                        println!("no command provided");
-                        // 否则，执行相应的命令
-                        // Command::new(parts[0]) 用于创建一个新的命令
-                        // args(&parts[1..]) 用于设置命令的参数
-                        // status() 用于执行命令并返回执行结果
-                    } else if let Err(e) = Command::new(parts[0]).args(&parts[1..]).status() {
-                        println!("failed to execute command `{}`: {}", cmd, e);
-                    }
-                } else {
-                    println!("unknown command: {input}");
-                }
-            }
-            // 如果读取失败，则打印错误信息
-            Err(error) => println!("error reading command: {error}"),
+// config_command 函数用于执行 kiga config 的子命令，出错时退出程序
+fn config_command(action: &ConfigAction) {
+    let fail = |message: String| -> ! {
+        println!("{message}");
+        std::process::exit(1);
+    };
+    match action {
+        ConfigAction::Get { key } => match config::get().lookup(key) {
+            Some((value, source)) => println!("{value}    # {source}"),
+            None if config::keys().contains(&key.as_str()) => println!("# {key} is not set"),
+            None => fail(config::unknown_key(key)),
+        },
+        ConfigAction::Set { key, value, global } => {
+            let path = if *global {
+                config::user_config_path()
+                    .unwrap_or_else(|| fail("Can't find your home directory".to_string()))
+            } else {
+                PathBuf::from(config::PROJECT_CONFIG)
+            };
+            config::set(&path, key, value).unwrap_or_else(|e| fail(e));
+            println!("Set {key} = {value} in {}", path.display());
         }
-    });
+        ConfigAction::List => config::get().print(),
+    }
 }
 
-// find_exercise 函数用于在练习列表中查找指定名称的练习
-fn find_exercise<'a>(name: &str, exercises: &'a [Exercise]) -> &'a Exercise {
-    // 如果提供的名称是 next，则查找第一个未完成的练习
-    if name.eq("next") {
-        exercises
-            .iter()
-            .find(|e| !e.looks_done())
-            .unwrap_or_else(|| {
-                println!("🎉 Congratulations! You have done all the exercises!");
-                println!("🔚 There are no more exercises to do next!");
-                std::process::exit(1)
-            })
-    } else {
+// select 函数用于根据标签和分类筛选练习，分类不存在时退出程序
+fn select<'a>(
+    selection: &Selection,
+    exercises: &'a [Exercise],
+    categories: &Categories,
+) -> Vec<&'a Exercise> {
+    if let Some(category) = selection.unknown_category(categories) {
+        println!("No category named '{category}' in info.toml!");
+        std::process::exit(1);
+    }
+    selection.apply(exercises, categories)
+}
+
+// find_exercise 函数用于在练习列表中查找指定名称、路径或别名的练习，找不到时退出程序
+fn find_exercise<'a>(name: &str, exercises: &'a [Exercise], root: &Root) -> &'a Exercise {
+    resolve(name, exercises, root).unwrap_or_else(|message| {
+        println!("{message}");
+        std::process::exit(1)
+    })
+}
+
+// resolve 函数查找指定名称、路径或别名的练习，找不到时返回要显示给用户的原因
+fn resolve<'a>(name: &str, exercises: &'a [Exercise], root: &Root) -> Result<&'a Exercise, String> {
+    // candidates 函数把候选练习逐行列出
+    let candidates = |exercises: Vec<&Exercise>| -> String {
         exercises
             .iter()
-            .find(|e| e.name == name)
-            .unwrap_or_else(|| {
-                println!("No exercise found for '{name}'!");
-                std::process::exit(1)
-            })
+            .map(|e| format!("\n  {}", e.name))
+            .collect()
+    };
+    match lookup::lookup(name, exercises, root, &Progress::load()) {
+        Lookup::Found(exercise) => Ok(exercise),
+        // 别名当前没有指向任何练习，返回原因
+        Lookup::Unavailable(reason) => Err(reason),
+        // 多个练习同样匹配时，列出候选而不是猜测
+        Lookup::Ambiguous(matches) => Err(format!(
+            "'{name}' is ambiguous, did you mean one of these?{}",
+            candidates(matches)
+        )),
+        Lookup::Unknown(suggestions) if suggestions.is_empty() => {
+            Err(format!("No exercise found for '{name}'!"))
+        }
+        Lookup::Unknown(suggestions) => Err(format!(
+            "No exercise found for '{name}', did you mean one of these?{}",
+            candidates(suggestions)
+        )),
     }
 }
 
@@ -399,16 +791,324 @@ fn find_exercise<'a>(name: &str, exercises: &'a [Exercise]) -> &'a Exercise {
 enum WatchStatus {
     Finished,
     Unfinished,
+    // info.toml 变化后重新加载的练习列表
+    Reload(Box<ExerciseList>),
+}
+
+// Follow 枚举类型用于表示文件变化时监视器重新检查哪些练习
+#[derive(Copy, Clone, Debug, PartialEq)]
+enum Follow {
+    // 先检查被修改的练习，再按顺序检查其余未完成的练习，全部完成时结束
+    Catalogue,
+    // 只检查被修改的练习，并显示它最新的输出
+    Changed,
+    // 只检查命令行中指定的练习，修改其他文件时重新检查全部指定的练习
+    Pinned,
+}
+
+// WatchShell 结构体保存逐行模式中用户命令和监视器共享的状态，重新加载练习列表时保留
+struct WatchShell {
+    // commands 接收用户输入的命令，第一次调用 start 时才开始读取
+    commands: Option<Commands>,
+    // current 是最近检查的练习，hint、run、edit 等命令作用于它
+    current: Option<PathBuf>,
+    // skipped 是用 skip 暂时跳过的练习，跟随练习列表时不再检查它们
+    skipped: Vec<PathBuf>,
+    // verbose 表示是否打印已完成练习的输出，可以用 verbose on|off 切换
+    verbose: bool,
+    // welcomed 表示是否已经显示过欢迎信息
+    welcomed: bool,
+}
+
+// Step 枚举类型表示处理一个命令之后监视器要做什么
+enum Step<'a> {
+    Nothing,
+    // 检查这些练习
+    Check(Vec<&'a Exercise>),
+    Quit,
+}
+
+impl WatchShell {
+    fn new(verbose: bool) -> Self {
+        WatchShell {
+            commands: None,
+            current: None,
+            skipped: Vec::new(),
+            verbose,
+            welcomed: false,
+        }
+    }
+
+    // 第一次调用时启动读取用户命令的线程
+    fn start(&mut self) {
+        if self.commands.is_none() {
+            self.commands = Some(Commands::spawn());
+        }
+    }
+
+    fn next_command(&self) -> Option<ShellCommand> {
+        self.commands.as_ref().and_then(Commands::try_next)
+    }
+
+    // done 函数在命令处理完之后让提示符回来
+    fn done(&self) {
+        if let Some(commands) = &self.commands {
+            commands.done();
+        }
+    }
+
+    fn current<'a>(&self, exercises: &[&'a Exercise]) -> Option<&'a Exercise> {
+        let current = self.current.as_ref()?;
+        exercises.iter().copied().find(|e| e.path == *current)
+    }
+
+    // next_pending 函数返回 after 之后（到末尾时从头开始）下一个未完成的练习，
+    // 只剩下跳过的练习时回到它们
+    fn next_pending<'a>(
+        &mut self,
+        exercises: &[&'a Exercise],
+        after: Option<&Exercise>,
+    ) -> Option<&'a Exercise> {
+        let start = after
+            .and_then(|a| exercises.iter().position(|e| std::ptr::eq(*e, a)))
+            .map_or(0, |i| i + 1);
+        let mut candidates = exercises[start..]
+            .iter()
+            .chain(&exercises[..start])
+            .copied()
+            .filter(|e| !after.is_some_and(|a| std::ptr::eq(*e, a)) && !e.known_done());
+        let next = candidates
+            .clone()
+            .find(|e| !self.skipped.contains(&e.path))
+            .or_else(|| candidates.next())?;
+        self.skipped.retain(|path| *path != next.path);
+        Some(next)
+    }
+
+    // handle 函数执行一个用户命令，返回接下来要检查的练习
+    fn handle<'a>(
+        &mut self,
+        command: ShellCommand,
+        exercises: &[&'a Exercise],
+        follow: Follow,
+    ) -> Step<'a> {
+        let current = self.current(exercises);
+        // 需要当前练习的命令在还没有检查过练习时打印说明
+        let require = |command: &str| {
+            if current.is_none() {
+                println!("There is no current exercise to {command} yet, save one first");
+            }
+            current
+        };
+        match command {
+            ShellCommand::Quit => return Step::Quit,
+            ShellCommand::Hint => {
+                if let Some(exercise) = require("show the hint of") {
+                    println!("{}", hint::next(exercise));
+                }
+            }
+            ShellCommand::Run => {
+                if let Some(exercise) = require("run") {
+                    return Step::Check(changed_targets(
+                        exercises,
+                        follow,
+                        &exercise.path,
+                        &self.skipped,
+                    ));
+                }
+            }
+            ShellCommand::Skip | ShellCommand::Next => {
+                if command == ShellCommand::Skip {
+                    let Some(exercise) = require("skip") else {
+                        return Step::Nothing;
+                    };
+                    println!(
+                        "Skipped {exercise}, `next` comes back to it when everything else is done"
+                    );
+                    self.skipped.push(exercise.path.clone());
+                }
+                match self.next_pending(exercises, current) {
+                    Some(next) => {
+                        self.current = Some(next.path.clone());
+                        return Step::Check(changed_targets(
+                            exercises,
+                            follow,
+                            &next.path,
+                            &self.skipped,
+                        ));
+                    }
+                    None => println!("There are no other unfinished exercises"),
+                }
+            }
+            ShellCommand::List => {
+                for exercise in exercises {
+                    let marker = if current.is_some_and(|c| std::ptr::eq(c, *exercise)) {
+                        '>'
+                    } else {
+                        ' '
+                    };
+                    // 需要编译才能判断的练习在检查之前显示为 unchecked
+                    let state = if self.skipped.contains(&exercise.path) {
+                        "skipped"
+                    } else {
+                        match exercise.known_state() {
+                            Some(State::Done) => "done",
+                            Some(State::Pending(_)) => "pending",
+                            None => "unchecked",
+                        }
+                    };
+                    println!("{marker} {state:<9} {exercise}");
+                }
+            }
+            ShellCommand::Reset => {
+                if let Some(exercise) = require("reset") {
+                    match snapshot::reset(exercise) {
+                        Ok(snapshot) => {
+                            println!("{}", snapshot::reset_message(exercise, &snapshot))
+                        }
+                        Err(message) => println!("{message}"),
+                    }
+                }
+            }
+            ShellCommand::Edit => {
+                if let Some(exercise) = require("edit") {
+                    if let Err(message) = run::edit(exercise) {
+                        println!("{message}");
+                    }
+                }
+            }
+            ShellCommand::Explain(Some(code)) => shell::explain(&code),
+            ShellCommand::Explain(None) => {
+                let Some(exercise) = require("explain") else {
+                    return Step::Nothing;
+                };
+                // 从上一次检查时编译器的输出中找出错误代码，不重新编译
+                let codes = exercise
+                    .build_errors()
+                    .map_or_else(Vec::new, |stderr| shell::error_codes(&stderr));
+                match codes.split_first() {
+                    None => println!("{exercise} has no compiler errors to explain"),
+                    Some((first, rest)) => {
+                        shell::explain(first);
+                        if !rest.is_empty() {
+                            println!("Also try `explain {}`", rest.join("`, `explain "));
+                        }
+                    }
+                }
+            }
+            ShellCommand::Verbose(verbose) => {
+                self.verbose = verbose;
+                println!("Verbose output is {}", if verbose { "on" } else { "off" });
+            }
+            ShellCommand::Clear => println!("\x1B[2J\x1B[1;1H"),
+            ShellCommand::Exec(words) => shell::exec(&words),
+            ShellCommand::Help => shell::print_help(),
+        }
+        Step::Nothing
+    }
+}
+
+// watch_project 函数监视整个项目，info.toml 变化时重新加载练习列表并继续监视
+#[allow(clippy::too_many_arguments)]
+fn watch_project(
+    root: &Root,
+    mut list: ExerciseList,
+    selection: &Selection,
+    names: &[String],
+    follow: Follow,
+    use_tui: bool,
+    verbose: bool,
+    success_hints: bool,
+) -> notify::Result<WatchStatus> {
+    let mut watcher = FileWatcher::new(&root.dir, &root.manifest, &list.include)?;
+    let mut shell = WatchShell::new(verbose);
+    for problem in alert::problems() {
+        warn!("{}", problem);
+    }
+    // 捕获 Ctrl-C，退出前取消检查（同时删除临时文件）并恢复终端
+    if let Err(e) = job::catch_interrupts() {
+        println!("Ctrl-C can't be caught, it may leave temporary files behind: {e}");
+    }
+    // notice 是重新加载的 info.toml 不能使用时，下一轮监视开始时显示的提醒
+    let mut notice: Option<String> = None;
+    loop {
+        // 指定了练习名称时只监视这些练习，否则按标签和分类筛选
+        let selected = if names.is_empty() {
+            select(selection, &list.exercises, &list.categories)
+        } else {
+            names
+                .iter()
+                .map(|name| find_exercise(name, &list.exercises, root))
+                .collect()
+        };
+        // 没有任何练习可以监视时直接返回，而不是打开一个空的界面
+        if selected.is_empty() {
+            println!("No exercises selected, there's nothing to watch");
+            return Ok(WatchStatus::Unfinished);
+        }
+        let status = if use_tui {
+            tui::watch(
+                &selected,
+                &list.categories,
+                follow,
+                verbose,
+                success_hints,
+                &watcher,
+                notice.take(),
+            )?
+        } else {
+            watch(
+                &selected,
+                &list.categories,
+                follow,
+                success_hints,
+                &watcher,
+                &mut shell,
+                notice.take(),
+            )?
+        };
+        match status {
+            WatchStatus::Reload(reloaded) => {
+                // 新的 info.toml 中找不到监视的分类或练习时，继续监视原来的练习，
+                // 就像 info.toml 有错误时一样
+                let unknown = match selection.unknown_category(&reloaded.categories) {
+                    Some(category) => Err(format!("No category named '{category}' in info.toml!")),
+                    None => names
+                        .iter()
+                        .try_for_each(|name| resolve(name, &reloaded.exercises, root).map(|_| ())),
+                };
+                if let Err(message) = unknown {
+                    let reason = message.lines().next().unwrap_or_default();
+                    notice = Some(format!(
+                        "{reason} Watching the exercises loaded before until info.toml is fixed"
+                    ));
+                    continue;
+                }
+                list = *reloaded;
+                hooks::init(list.hooks.clone());
+                watcher.set_include(&list.include)?;
+            }
+            status => return Ok(status),
+        }
+    }
 }
 
 // watch 函数用于启动一个监视器，用于监视文件系统事件
 fn watch(
-    // exercises 是一个 &[Exercise] 类型的变量，用于存储练习列表
-    exercises: &[Exercise],
-    // verbose 是一个 bool 类型的变量，用于表示是否打印练习列表
-    verbose: bool,
+    // exercises 是一个 &[&Exercise] 类型的变量，用于存储被选中的练习列表
+    exercises: &[&Exercise],
+    // categories 是练习的分类，用于按分类显示进度
+    categories: &Categories,
+    // follow 表示文件变化时重新检查哪些练习
+    follow: Follow,
     // success_hints 是一个 bool 类型的变量，用于表示是否在练习完成时打印提示
     success_hints: bool,
+    // watcher 监视练习和 info.toml 的变化
+    watcher: &FileWatcher,
+    // shell 读取用户命令，并保存当前练习等状态
+    shell: &mut WatchShell,
+    // notice 是清屏后首先显示的提醒
+    notice: Option<String>,
     // watch 函数返回一个 Result<WatchStatus> 类型的结果
 ) -> notify::Result<WatchStatus> {
     /* Clears the terminal with an ANSI escape code.
@@ -417,88 +1117,285 @@ fn watch(
         println!("\x1Bc");
     }
 
-    // 创建一个新的通道，用于在监视器和主线程之间传递消息
-    let (tx, rx) = channel();
-    // 创建一个新的原子布尔值，用于表示是否退出程序
-    let should_quit = Arc::new(AtomicBool::new(false));
-
-    // 创建一个新的监视器，用于监视文件系统事件
-    // tx 是一个 Sender<DebouncedEvent> 类型的变量，用于向监视器发送消息
-    // Duration::from_secs(1) 表示监视器每隔 1 秒检查一次文件系统事件
-    let mut watcher: RecommendedWatcher = Watcher::new(tx, Duration::from_secs(1))?;
-    // 将当前目录下的所有文件和子目录添加到监视器中
-    watcher.watch(Path::new("./exercises"), RecursiveMode::Recursive)?;
-
+    // 第一次检查：跟随练习列表时验证没有跳过的练习，指定练习时检查全部指定的练习
+    let initial: Vec<&Exercise> = match follow {
+        Follow::Catalogue => exercises
+            .iter()
+            .copied()
+            .filter(|e| !shell.skipped.contains(&e.path))
+            .collect(),
+        Follow::Pinned => exercises.to_vec(),
+        Follow::Changed => Vec::new(),
+    };
     // 清空终端
     clear_screen();
+    if let Some(notice) = notice {
+        warn!("{}", notice);
+    }
+    // focus 模式下先不检查，等待练习被修改
+    if follow == Follow::Changed {
+        println!(
+            "Watching {} exercises, save one to run it.",
+            exercises.len()
+        );
+    }
 
-    // to_owned_hint 函数用于将 Exercise 类型的变量转换为 String 类型的变量
-    let to_owned_hint = |t: &Exercise| t.hint.to_owned();
-    // failed_exercise_hint 是一个 Arc<Mutex<Option<String>>> 类型的变量，用于存储当前练习的提示
-    let failed_exercise_hint = match verify(
-        // exercises.iter() 用于创建一个迭代器，用于遍历练习列表
-        exercises.iter(),
-        (0, exercises.len()),
-        verbose,
-        success_hints,
-    ) {
-        Ok(_) => return Ok(WatchStatus::Finished),
-        Err(exercise) => Arc::new(Mutex::new(Some(to_owned_hint(exercise)))),
+    // finish 函数处理一次检查的结果，全部完成时返回 Finished，还有跳过的练习时提醒用户。
+    // 跟随练习列表时像 verify 一样按分类显示进度
+    let finish = |shell: &mut WatchShell, result: Result<(), &Exercise>| {
+        if follow == Follow::Catalogue {
+            category::print_progress(exercises, categories);
+        }
+        match result {
+            Ok(()) if follow == Follow::Catalogue && shell.skipped.is_empty() => {
+                Some(WatchStatus::Finished)
+            }
+            Ok(()) if follow == Follow::Catalogue => {
+                println!(
+                    "Everything else is done, type `next` to go back to the {} skipped exercises",
+                    shell.skipped.len()
+                );
+                None
+            }
+            Ok(()) => None,
+            Err(exercise) => {
+                shell.current = Some(exercise.path.clone());
+                None
+            }
+        }
     };
 
-    // 启动一个新的线程，用于监听用户输入的命令
-    spawn_watch_shell(&failed_exercise_hint, Arc::clone(&should_quit));
-    loop {
-        // 接收来自监视器的消息
-        match rx.recv_timeout(Duration::from_secs(1)) {
-            Ok(event) => match event {
-                // 如果接收到的消息是 Create、Chmod 或 Write，则检查是否有练习完成
-                DebouncedEvent::Create(b) | DebouncedEvent::Chmod(b) | DebouncedEvent::Write(b) => {
-                    // 如果文件的扩展名是 rs，则检查是否有练习完成
-                    if b.extension() == Some(OsStr::new("rs")) && b.exists() {
-                        // 将文件的路径转换为绝对路径
-                        let filepath = b.as_path().canonicalize().unwrap();
-                        // 从练习列表中查找指定路径的练习
-                        let pending_exercises = exercises
-                            .iter()
-                            // 如果练习已完成，则将其从练习列表中移除
-                            .find(|e| filepath.ends_with(&e.path))
-                            .into_iter()
-                            .chain(
-                                exercises
-                                    .iter()
-                                    .filter(|e| !e.looks_done() && !filepath.ends_with(&e.path)),
-                            );
-                            // 统计已完成的练习数量
-                        let num_done = exercises.iter().filter(|e| e.looks_done()).count();
-                        clear_screen();
-                        match verify(
-                            pending_exercises,
-                            (num_done, exercises.len()),
-                            verbose,
-                            success_hints,
-                        ) {
-                            Ok(_) => return Ok(WatchStatus::Finished),
-                            Err(exercise) => {
-                                let mut failed_exercise_hint = failed_exercise_hint.lock().unwrap();
-                                *failed_exercise_hint = Some(to_owned_hint(exercise));
-                            }
+    // 启动读取用户命令的线程，检查进行时也可以输入 quit
+    shell.start();
+    // 检查在后台线程中进行，主线程继续接收文件变化，必要时取消正在进行的检查
+    let verbose_at_start = shell.verbose;
+    thread::scope(|scope| {
+        // spawn_checks 在后台开始检查 targets
+        let spawn_checks = move |scope, targets: Vec<_>, verbose| {
+            // 统计已知完成的练习数量，主线程不为此编译练习
+            let num_done = exercises.iter().filter(|e| e.known_done()).count();
+            clear_screen();
+            let target_paths = paths(&targets);
+            Job::spawn(scope, target_paths, move || {
+                run_checks(
+                    &targets,
+                    follow,
+                    (num_done, exercises.len()),
+                    verbose,
+                    success_hints,
+                    true,
+                )
+            })
+        };
+        // queued 是等待正在进行的检查结束后才开始的检查
+        let mut queued: VecDeque<Vec<&Exercise>> = VecDeque::new();
+        let mut job = (!initial.is_empty()).then(|| {
+            let targets = paths(&initial);
+            Job::spawn(scope, targets, move || {
+                run_checks(
+                    &initial,
+                    follow,
+                    (0, exercises.len()),
+                    verbose_at_start,
+                    success_hints,
+                    false,
+                )
+            })
+        });
+        loop {
+            // 后台检查结束时处理它的结果
+            if job.as_ref().is_some_and(Job::is_finished) {
+                let result = job.take().map(Job::join).expect("a job is running");
+                if let Some(status) = finish(shell, result) {
+                    return Ok(status);
+                }
+            }
+            // 没有正在进行的检查时开始排队中的下一个检查
+            if job.is_none() {
+                if let Some(targets) = queued.pop_front() {
+                    job = Some(spawn_checks(scope, targets, shell.verbose));
+                }
+            }
+            // 第一次检查结束后才显示欢迎信息
+            if job.is_none() && !shell.welcomed {
+                // 文件系统事件不可用或指定了 --poll 时，说明正在轮询
+                if let Some(note) = watcher.polling_note() {
+                    println!("{note}");
+                }
+                println!("Welcome to watch mode! You can type ':help' to get an overview of the commands you can use here.");
+                shell.welcomed = true;
+            }
+            // 没有在读取命令时按下的 Ctrl-C 同样退出
+            if job::interrupted() {
+                if let Some(job) = job.take() {
+                    job.cancel();
+                }
+                return Ok(WatchStatus::Unfinished);
+            }
+            // checks 是这一轮要开始的检查，来自用户命令和文件变化
+            let mut checks = Vec::new();
+            while let Some(command) = shell.next_command() {
+                let step = shell.handle(command, exercises, follow);
+                shell.done();
+                match step {
+                    Step::Nothing => {}
+                    Step::Check(targets) => checks.push(targets),
+                    Step::Quit => {
+                        if let Some(job) = job.take() {
+                            job.cancel();
                         }
+                        return Ok(WatchStatus::Unfinished);
                     }
                 }
-                _ => {}
-            },
-            // 如果接收到的消息是超时，则继续循环
-            Err(RecvTimeoutError::Timeout) => {
-                // the timeout expired, just check the `should_quit` variable below then loop again
             }
-            Err(e) => println!("watch error: {e:?}"),
+
+            // 等待来自监视器的变化
+            let changes = match watcher.changes(Duration::from_millis(200)) {
+                Ok(changes) => changes,
+                Err(e) => {
+                    println!("watch error: {e:?}");
+                    Vec::new()
+                }
+            };
+            for change in changes {
+                let filepath = match change {
+                    // info.toml 变化时重新加载练习列表，有错误时继续使用原来的列表
+                    Change::Catalogue => match catalogue::load(watcher.manifest()) {
+                        Ok((list, _)) => {
+                            if let Some(job) = job.take() {
+                                job.cancel();
+                            }
+                            return Ok(WatchStatus::Reload(Box::new(list)));
+                        }
+                        Err(diagnostics) => {
+                            diagnostics
+                                .iter()
+                                .filter(|d| d.is_error())
+                                .for_each(|d| println!("{d}\n"));
+                            warn!(
+                                "{}",
+                                "info.toml has errors, watching the exercises loaded before"
+                            );
+                            continue;
+                        }
+                    },
+                    Change::Source(filepath) => filepath,
+                };
+                let targets = changed_targets(exercises, follow, &filepath, &shell.skipped);
+                // focus 和指定练习的模式下，没有要检查的练习时什么也不做
+                if targets.is_empty() && follow != Follow::Catalogue {
+                    continue;
+                }
+                checks.push(targets);
+            }
+            for targets in checks {
+                // 正在检查同一个练习时取消旧的检查，否则排队等它完成再开始新的检查，
+                // 新的检查包含了排队中的某个检查时，那个检查不再需要
+                let target_paths = paths(&targets);
+                if job
+                    .as_ref()
+                    .is_some_and(|running| running.overlaps(&target_paths))
+                {
+                    job.take().expect("a job is running").cancel();
+                }
+                queued.retain(|waiting| !paths(waiting).iter().all(|p| target_paths.contains(p)));
+                if job.is_none() && queued.is_empty() {
+                    job = Some(spawn_checks(scope, targets, shell.verbose));
+                } else {
+                    queued.push_back(targets);
+                }
+            }
         }
-        // Check if we need to exit
-        if should_quit.load(Ordering::SeqCst) {
-            return Ok(WatchStatus::Unfinished);
+    })
+}
+
+// changed_targets 函数返回文件 filepath 变化后要检查的练习，跟随练习列表时不检查跳过的练习
+fn changed_targets<'a>(
+    exercises: &[&'a Exercise],
+    follow: Follow,
+    filepath: &Path,
+    skipped: &[PathBuf],
+) -> Vec<&'a Exercise> {
+    match follow {
+        Follow::Catalogue => {
+            // 从练习列表中查找指定路径的练习，前提是它依赖的练习都已完成
+            let changed = exercises
+                .iter()
+                .copied()
+                .find(|e| filepath.ends_with(&e.path) && graph::requirements_met(e, exercises));
+            // 先验证被修改的练习，再按拓扑顺序验证其余未完成的练习
+            changed
+                .into_iter()
+                .chain(exercises.iter().copied().filter(|e| {
+                    !e.known_done()
+                        && !skipped.contains(&e.path)
+                        && !changed.is_some_and(|c| std::ptr::eq(c, *e))
+                }))
+                .collect()
+        }
+        // focus 和指定练习的模式下，只重新检查被修改的（或全部指定的）练习
+        Follow::Changed | Follow::Pinned => {
+            let changed = exercises
+                .iter()
+                .copied()
+                .find(|e| filepath.ends_with(&e.path));
+            match (changed, follow) {
+                (Some(exercise), _) => vec![exercise],
+                (None, Follow::Pinned) => exercises.to_vec(),
+                (None, _) => Vec::new(),
+            }
+        }
+    }
+}
+
+// run_checks 函数在后台检查练习。跟随练习列表时按顺序验证，在第一个未完成的练习处停下，
+// 否则逐个检查并打印最新的输出，返回最后一个练习以显示它的提示。
+// alert 为 true 时按 notify.* 设置通知检查的结果
+fn run_checks<'a>(
+    targets: &[&'a Exercise],
+    follow: Follow,
+    progress: (usize, usize),
+    verbose: bool,
+    success_hints: bool,
+    alert: bool,
+) -> Result<(), &'a Exercise> {
+    let (result, checked) = match follow {
+        Follow::Catalogue => {
+            let result = verify(
+                targets.iter().copied(),
+                progress,
+                verbose,
+                success_hints,
+                Origin::Watch,
+            );
+            // verify 在第一个未完成的练习处停下，它之前的练习都已完成
+            let checked = match (targets.first(), result) {
+                (None, _) => Vec::new(),
+                (Some(first), Ok(())) => vec![(*first, Outcome::Passed)],
+                (Some(first), Err(e)) if std::ptr::eq(*first, e) => vec![(e, Outcome::Failed)],
+                (Some(first), Err(e)) => vec![(*first, Outcome::Passed), (e, Outcome::Pending)],
+            };
+            (result, checked)
+        }
+        Follow::Changed | Follow::Pinned => {
+            let checked: Vec<_> = targets
+                .iter()
+                .map(|e| (*e, print_check(e, verbose)))
+                .collect();
+            (targets.last().map_or(Ok(()), |last| Err(*last)), checked)
+        }
+    };
+    if alert && !job::cancelled() {
+        if let Some(event) = alert::event(&checked, follow == Follow::Catalogue) {
+            alert::notify(&event);
         }
     }
+    result
+}
+
+// paths 函数返回练习的路径，用来判断两次检查是否涉及同一个练习
+fn paths(exercises: &[&Exercise]) -> Vec<PathBuf> {
+    exercises.iter().map(|e| e.path.clone()).collect()
 }
 
 // rustc_exists 函数用于检查是否安装了 Rust 编译器
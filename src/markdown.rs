@@ -0,0 +1,292 @@
+use console::Style;
+use pulldown_cmark::{BrokenLink, CodeBlockKind, CowStr, Event, Options, Parser, Tag, TagEnd};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::OnceLock;
+
+// The crates whose documentation ships with rustup's rust-docs component
+const DOC_CRATES: &[&str] = &["std", "core", "alloc", "proc_macro", "test"];
+// The kinds of items rustdoc writes a `<kind>.<name>.html` page for
+const ITEM_KINDS: &[&str] = &[
+    "struct",
+    "enum",
+    "trait",
+    "fn",
+    "macro",
+    "type",
+    "constant",
+    "static",
+    "union",
+    "primitive",
+    "keyword",
+];
+const ONLINE_DOCS: &str = "https://doc.rust-lang.org";
+
+// Render markdown for the terminal. Horizontal rules (`---`) split the text
+// into parts, so a hint file can hold several levels. Without `styled`, the
+// text is laid out the same but carries no colors, as the watch mode TUI
+// draws its own
+pub fn render(source: &str, styled: bool) -> Vec<String> {
+    let link_std_paths = |link: BrokenLink| {
+        let target = doc_url(link.reference.trim_matches('`'))?;
+        Some((CowStr::from(target), CowStr::from("")))
+    };
+    let parser = Parser::new_with_broken_link_callback(
+        source,
+        Options::ENABLE_STRIKETHROUGH,
+        Some(link_std_paths),
+    );
+    let mut renderer = Renderer {
+        styled,
+        ..Renderer::default()
+    };
+    for event in parser {
+        renderer.event(event);
+    }
+    renderer.finish()
+}
+
+#[derive(Default)]
+struct Renderer {
+    styled: bool,
+    parts: Vec<String>,
+    out: String,
+    bold: usize,
+    italic: usize,
+    strike: usize,
+    quote: usize,
+    heading: bool,
+    code_block: bool,
+    // The next number of each ordered list we're in, None for bullets
+    lists: Vec<Option<u64>>,
+    // The destination of the link we're in, printed after its text
+    link: Option<String>,
+}
+
+impl Renderer {
+    fn event(&mut self, event: Event) {
+        match event {
+            Event::Start(tag) => self.start(tag),
+            Event::End(tag) => self.end(tag),
+            Event::Text(text) if self.code_block => {
+                for line in text.lines() {
+                    let line = format!("    {line}");
+                    self.push_styled(&line, Style::new().cyan());
+                    self.out.push('\n');
+                }
+            }
+            Event::Text(text) => {
+                let style = self.style();
+                self.push_styled(&text, style);
+            }
+            Event::Code(code) => self.push_styled(&code, Style::new().yellow()),
+            Event::SoftBreak => self.out.push(' '),
+            Event::HardBreak => self.out.push('\n'),
+            Event::Rule => {
+                let part = std::mem::take(&mut self.out);
+                self.parts.push(part.trim_end().to_string());
+            }
+            Event::Html(html) | Event::InlineHtml(html) => self.out.push_str(&html),
+            Event::TaskListMarker(done) => self.out.push_str(if done { "[x] " } else { "[ ] " }),
+            _ => {}
+        }
+    }
+
+    fn start(&mut self, tag: Tag) {
+        match tag {
+            Tag::Heading { .. } => self.heading = true,
+            Tag::BlockQuote(_) => self.quote += 1,
+            Tag::CodeBlock(kind) => {
+                self.code_block = true;
+                if let CodeBlockKind::Fenced(lang) = kind {
+                    if !lang.is_empty() {
+                        self.push_styled(&format!("    // {lang}\n"), Style::new().dim());
+                    }
+                }
+            }
+            Tag::List(first) => {
+                if !self.lists.is_empty() && !self.out.ends_with('\n') {
+                    self.out.push('\n');
+                }
+                self.lists.push(first);
+            }
+            Tag::Item => {
+                let indent = "  ".repeat(self.lists.len().saturating_sub(1));
+                let marker = match self.lists.last_mut() {
+                    Some(Some(number)) => {
+                        *number += 1;
+                        format!("{}.", *number - 1)
+                    }
+                    _ => "•".to_string(),
+                };
+                self.out.push_str(&format!("{indent}{marker} "));
+            }
+            Tag::Emphasis => self.italic += 1,
+            Tag::Strong => self.bold += 1,
+            Tag::Strikethrough => self.strike += 1,
+            Tag::Link { dest_url, .. } => {
+                let url = doc_url(&dest_url).unwrap_or_else(|| dest_url.to_string());
+                self.link = Some(url);
+            }
+            _ => {}
+        }
+    }
+
+    fn end(&mut self, tag: TagEnd) {
+        match tag {
+            TagEnd::Paragraph => {
+                self.out
+                    .push_str(if self.lists.is_empty() { "\n\n" } else { "\n" })
+            }
+            TagEnd::Heading(_) => {
+                self.heading = false;
+                self.out.push_str("\n\n");
+            }
+            TagEnd::BlockQuote(_) => self.quote -= 1,
+            TagEnd::CodeBlock => {
+                self.code_block = false;
+                self.out.push('\n');
+            }
+            TagEnd::List(_) => {
+                self.lists.pop();
+                if self.lists.is_empty() {
+                    self.out.push('\n');
+                }
+            }
+            TagEnd::Item if !self.out.ends_with('\n') => self.out.push('\n'),
+            TagEnd::Emphasis => self.italic -= 1,
+            TagEnd::Strong => self.bold -= 1,
+            TagEnd::Strikethrough => self.strike -= 1,
+            TagEnd::Link => {
+                if let Some(url) = self.link.take() {
+                    self.push_styled(&format!(" <{url}>"), Style::new().dim());
+                }
+            }
+            _ => {}
+        }
+    }
+
+    // The style of running text at this point
+    fn style(&self) -> Style {
+        let mut style = Style::new();
+        if self.heading {
+            style = style.bold().underlined();
+        }
+        if self.bold > 0 {
+            style = style.bold();
+        }
+        if self.italic > 0 || self.quote > 0 {
+            style = style.italic();
+        }
+        if self.strike > 0 {
+            style = style.dim();
+        }
+        if self.link.is_some() {
+            style = style.underlined();
+        }
+        style
+    }
+
+    fn push_styled(&mut self, text: &str, style: Style) {
+        if self.styled {
+            self.out.push_str(&style.apply_to(text).to_string());
+        } else {
+            self.out.push_str(text);
+        }
+    }
+
+    fn finish(mut self) -> Vec<String> {
+        self.parts.push(self.out.trim_end().to_string());
+        self.parts.retain(|part| !part.trim().is_empty());
+        self.parts
+    }
+}
+
+// Where the documentation of a path like `std::sync::Once` is: the page in
+// the locally installed rust-docs when there is one, or else a search of the
+// online docs. None for anything that isn't such a path
+fn doc_url(path: &str) -> Option<String> {
+    let segments: Vec<&str> = path.split("::").collect();
+    let valid = segments
+        .iter()
+        .all(|s| !s.is_empty() && s.chars().all(|c| c.is_alphanumeric() || c == '_'));
+    if !valid || !DOC_CRATES.contains(&segments[0]) {
+        return None;
+    }
+    let local = local_docs().and_then(|docs| local_page(docs, &segments));
+    Some(local.unwrap_or_else(|| format!("{ONLINE_DOCS}/{}/?search={path}", segments[0])))
+}
+
+// The page documenting an item, or a method of one
+fn local_page(docs: &Path, segments: &[&str]) -> Option<String> {
+    if let Some(page) = item_page(docs, segments) {
+        return Some(file_url(&page));
+    }
+    let (method, item) = segments.split_last()?;
+    let page = item_page(docs, item)?;
+    Some(format!("{}#method.{method}", file_url(&page)))
+}
+
+fn item_page(docs: &Path, segments: &[&str]) -> Option<PathBuf> {
+    let dir: PathBuf = segments.iter().collect();
+    let module = docs.join(&dir).join("index.html");
+    if module.is_file() {
+        return Some(module);
+    }
+    let (name, parent) = segments.split_last()?;
+    let parent: PathBuf = docs.join(parent.iter().collect::<PathBuf>());
+    ITEM_KINDS
+        .iter()
+        .map(|kind| parent.join(format!("{kind}.{name}.html")))
+        .find(|page| page.is_file())
+}
+
+fn file_url(path: &Path) -> String {
+    format!("file://{}", path.display())
+}
+
+// The html documentation installed with the toolchain, as `rustup doc` opens
+fn local_docs() -> Option<&'static Path> {
+    static DOCS: OnceLock<Option<PathBuf>> = OnceLock::new();
+    DOCS.get_or_init(|| {
+        let output = Command::new("rustc")
+            .args(["--print", "sysroot"])
+            .output()
+            .ok()?;
+        let sysroot = String::from_utf8(output.stdout).ok()?;
+        let docs = Path::new(sysroot.trim()).join("share/doc/rust/html");
+        docs.is_dir().then_some(docs)
+    })
+    .as_deref()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_render_plain() {
+        let source = "# Threads\n\nUse *one* `Once`:\n\n- first\n- second\n\n```rust\nlet x = 1;\n```\n\n---\n\nAlmost **there**.";
+        let parts = render(source, false);
+        assert_eq!(
+            parts,
+            [
+                "Threads\n\nUse one Once:\n\n• first\n• second\n\n    // rust\n    let x = 1;",
+                "Almost there."
+            ]
+        );
+    }
+
+    #[test]
+    fn test_std_paths_are_linked() {
+        assert_eq!(doc_url("std::sync::Once;"), None);
+        assert_eq!(doc_url("serde::Deserialize"), None);
+        let url = doc_url("std::sync::Once").unwrap();
+        assert!(
+            url.ends_with("/std/sync/struct.Once.html")
+                || url == "https://doc.rust-lang.org/std/?search=std::sync::Once"
+        );
+        let parts = render("See [`std::sync::Once`].", false);
+        assert_eq!(parts, [format!("See std::sync::Once <{url}>.")]);
+    }
+}
@@ -0,0 +1,383 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt::{self, Display, Formatter};
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::sync::{Mutex, PoisonError};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+const PROGRESS_PATH: &str = ".kiga/progress.json";
+// How many attempts are kept per exercise; older ones are only counted
+const MAX_RECENT_ATTEMPTS: usize = 100;
+
+// The command that produced an attempt
+#[derive(Serialize, Deserialize, Copy, Clone, Debug, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum Origin {
+    Verify,
+    Run,
+    Watch,
+}
+
+// How an attempt ended
+#[derive(Serialize, Deserialize, Copy, Clone, Debug, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum Outcome {
+    // The exercise built, ran and meets its completion criteria
+    Passed,
+    // The exercise built and ran, but isn't done yet
+    Pending,
+    // The exercise failed to compile or run
+    Failed,
+}
+
+// A single compile and run of an exercise
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct Attempt {
+    // Seconds since the Unix epoch
+    pub at: u64,
+    pub origin: Origin,
+    pub outcome: Outcome,
+    pub duration_ms: u64,
+}
+
+// Everything remembered about one exercise
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct History {
+    pub attempts: u32,
+    pub passes: u32,
+    // The number of attempts it took to pass the first time
+    pub attempts_to_pass: Option<u32>,
+    pub last_passed: Option<u64>,
+    pub total_ms: u64,
+    // The most recent attempts, oldest first
+    pub recent: Vec<Attempt>,
+    // How many levels of the hint have been revealed
+    #[serde(default)]
+    pub hints_seen: usize,
+}
+
+// The local progress database stored in `.kiga/progress.json`
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct Progress {
+    pub exercises: BTreeMap<String, History>,
+}
+
+impl Progress {
+    // Load the progress database, starting fresh if there is none yet
+    pub fn load() -> Progress {
+        Self::load_from(Path::new(PROGRESS_PATH))
+    }
+
+    fn load_from(path: &Path) -> Progress {
+        match fs::read_to_string(path) {
+            Ok(json) => serde_json::from_str(&json).unwrap_or_else(|e| {
+                warn!("Ignoring the unreadable progress file: {}", e);
+                Progress::default()
+            }),
+            Err(_) => Progress::default(),
+        }
+    }
+
+    fn save_to(&self, path: &Path) -> io::Result<()> {
+        let json = serde_json::to_vec_pretty(self).expect("Failed to serialize to JSON");
+        // Write to a temporary file first so an interrupted write
+        // can't leave a truncated database behind
+        let tmp = path.with_extension("json.tmp");
+        fs::write(&tmp, json)?;
+        fs::rename(tmp, path)
+    }
+
+    // The attempts at an exercise, None until there is one
+    pub fn history(&self, name: &str) -> Option<&History> {
+        self.exercises.get(name).filter(|h| h.attempts > 0)
+    }
+
+    pub fn hints_seen(&self, name: &str) -> usize {
+        self.exercises.get(name).map_or(0, |h| h.hints_seen)
+    }
+
+    // Count one more of an exercise's hint levels as seen, up to all of them,
+    // and return how many have been
+    pub fn reveal_hint(&mut self, name: &str, levels: usize) -> usize {
+        let history = self.exercises.entry(name.to_string()).or_default();
+        history.hints_seen = (history.hints_seen + 1).min(levels);
+        history.hints_seen
+    }
+
+    pub fn record(&mut self, name: &str, attempt: Attempt) {
+        let history = self.exercises.entry(name.to_string()).or_default();
+        history.attempts += 1;
+        history.total_ms += attempt.duration_ms;
+        if attempt.outcome == Outcome::Passed {
+            history.passes += 1;
+            history.last_passed = Some(attempt.at);
+            history.attempts_to_pass.get_or_insert(history.attempts);
+        }
+        history.recent.push(attempt);
+        if history.recent.len() > MAX_RECENT_ATTEMPTS {
+            let excess = history.recent.len() - MAX_RECENT_ATTEMPTS;
+            history.recent.drain(..excess);
+        }
+    }
+}
+
+// Reveal the next hint level of an exercise in the progress database.
+// Returns how many levels have been seen, the newest being the one to show
+pub fn reveal_hint(name: &str, levels: usize) -> usize {
+    update(Path::new(PROGRESS_PATH), |progress| {
+        progress.reveal_hint(name, levels)
+    })
+}
+
+// Record an attempt in the progress database.
+// Failing to persist progress is reported but never fatal
+pub fn record(name: &str, origin: Origin, outcome: Outcome, duration: Duration) {
+    let attempt = Attempt {
+        at: now(),
+        origin,
+        outcome,
+        duration_ms: duration.as_millis() as u64,
+    };
+    update(Path::new(PROGRESS_PATH), |progress| {
+        progress.record(name, attempt)
+    });
+}
+
+// Load the database, change it and save it again. Background checks record
+// attempts while the main thread may too, so one update finishes before the
+// next loads, or else it could overwrite what the other saved
+fn update<T>(path: &Path, change: impl FnOnce(&mut Progress) -> T) -> T {
+    static UPDATING: Mutex<()> = Mutex::new(());
+    let _updating = UPDATING.lock().unwrap_or_else(PoisonError::into_inner);
+    let mut progress = Progress::load_from(path);
+    let result = change(&mut progress);
+    let saved = path
+        .parent()
+        .map_or(Ok(()), fs::create_dir_all)
+        .and_then(|()| progress.save_to(path));
+    if let Err(e) = saved {
+        warn!("Failed to save your progress: {}", e);
+    }
+    result
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+impl Display for Origin {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            Origin::Verify => write!(f, "verify"),
+            Origin::Run => write!(f, "run"),
+            Origin::Watch => write!(f, "watch"),
+        }
+    }
+}
+
+impl Display for Outcome {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            Outcome::Passed => write!(f, "passed"),
+            Outcome::Pending => write!(f, "pending"),
+            Outcome::Failed => write!(f, "failed"),
+        }
+    }
+}
+
+// Format a Unix timestamp as a UTC date and time
+pub fn format_timestamp(secs: u64) -> String {
+    let days = (secs / 86_400) as i64;
+    let rem = secs % 86_400;
+    // Civil date from days since the epoch, after Howard Hinnant's algorithm
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1_460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    format!(
+        "{year:04}-{month:02}-{day:02} {:02}:{:02}:{:02} UTC",
+        rem / 3_600,
+        rem % 3_600 / 60,
+        rem % 60
+    )
+}
+
+// Format a duration in milliseconds for humans
+pub fn format_duration(ms: u64) -> String {
+    if ms < 1_000 {
+        format!("{ms}ms")
+    } else if ms < 60_000 {
+        format!("{:.1}s", ms as f64 / 1_000.0)
+    } else {
+        format!("{}m{:02}s", ms / 60_000, ms % 60_000 / 1_000)
+    }
+}
+
+// Print every remembered attempt of an exercise, newest first
+pub fn print_history(name: &str, history: &History) {
+    println!(
+        "{name}: {} attempts, {} passes, {} in total",
+        history.attempts,
+        history.passes,
+        format_duration(history.total_ms)
+    );
+    if let Some(n) = history.attempts_to_pass {
+        println!("First passed after {n} attempts");
+    }
+    if let Some(at) = history.last_passed {
+        println!("Last passed on {}", format_timestamp(at));
+    }
+    if history.hints_seen > 0 {
+        println!("Hint levels seen: {}", history.hints_seen);
+    }
+    println!();
+    println!(
+        "{:<23}\t{:<6}\t{:<7}\t{:>8}",
+        "When", "Origin", "Outcome", "Duration"
+    );
+    for attempt in history.recent.iter().rev() {
+        println!(
+            "{:<23}\t{:<6}\t{:<7}\t{:>8}",
+            format_timestamp(attempt.at),
+            attempt.origin,
+            attempt.outcome,
+            format_duration(attempt.duration_ms)
+        );
+    }
+}
+
+// Print a summary of the whole progress database
+pub fn print_stats(progress: &Progress, total_exercises: usize) {
+    let histories = progress.exercises.values().filter(|h| h.attempts > 0);
+    let attempted = histories.clone().count();
+    let attempts: u32 = histories.clone().map(|h| h.attempts).sum();
+    let total_ms: u64 = histories.clone().map(|h| h.total_ms).sum();
+    let passed = histories.clone().filter(|h| h.passes > 0).count();
+    println!("Exercises attempted: {attempted} / {total_exercises}");
+    println!("Exercises passed:    {passed} / {total_exercises}");
+    println!("Attempts:            {attempts}");
+    println!("Time spent building: {}", format_duration(total_ms));
+    if attempted == 0 {
+        return;
+    }
+    println!();
+    println!(
+        "{:<17}\t{:>8}\t{:>6}\t{:>8}\t{:<23}",
+        "Name", "Attempts", "Passes", "Time", "Last passed"
+    );
+    for (name, history) in progress.exercises.iter().filter(|(_, h)| h.attempts > 0) {
+        println!(
+            "{name:<17}\t{:>8}\t{:>6}\t{:>8}\t{:<23}",
+            history.attempts,
+            history.passes,
+            format_duration(history.total_ms),
+            history
+                .last_passed
+                .map(format_timestamp)
+                .unwrap_or_default()
+        );
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn attempt(at: u64, outcome: Outcome) -> Attempt {
+        Attempt {
+            at,
+            origin: Origin::Verify,
+            outcome,
+            duration_ms: 10,
+        }
+    }
+
+    #[test]
+    fn test_record_counts_attempts_to_pass() {
+        let mut progress = Progress::default();
+        progress.record("intro", attempt(1, Outcome::Failed));
+        progress.record("intro", attempt(2, Outcome::Pending));
+        progress.record("intro", attempt(3, Outcome::Passed));
+        progress.record("intro", attempt(4, Outcome::Passed));
+
+        let history = progress.history("intro").unwrap();
+        assert_eq!(history.attempts, 4);
+        assert_eq!(history.passes, 2);
+        assert_eq!(history.attempts_to_pass, Some(3));
+        assert_eq!(history.last_passed, Some(4));
+        assert_eq!(history.total_ms, 40);
+    }
+
+    #[test]
+    fn test_recent_attempts_are_capped() {
+        let mut progress = Progress::default();
+        for at in 0..(MAX_RECENT_ATTEMPTS as u64 + 5) {
+            progress.record("intro", attempt(at, Outcome::Failed));
+        }
+
+        let history = progress.history("intro").unwrap();
+        assert_eq!(history.recent.len(), MAX_RECENT_ATTEMPTS);
+        assert_eq!(history.recent[0].at, 5);
+        assert_eq!(history.attempts, MAX_RECENT_ATTEMPTS as u32 + 5);
+    }
+
+    #[test]
+    fn test_hints_are_revealed_one_level_at_a_time() {
+        let mut progress = Progress::default();
+        assert_eq!(progress.reveal_hint("intro", 2), 1);
+        assert_eq!(progress.reveal_hint("intro", 2), 2);
+        assert_eq!(progress.reveal_hint("intro", 2), 2);
+        assert_eq!(progress.hints_seen("intro"), 2);
+        // Seeing hints isn't an attempt
+        assert_eq!(progress.history("intro"), None);
+    }
+
+    #[test]
+    fn test_save_and_load_round_trip() {
+        let path = std::env::temp_dir().join(format!("kiga_progress_{}.json", std::process::id()));
+        let mut progress = Progress::default();
+        progress.record("intro", attempt(7, Outcome::Passed));
+        progress.save_to(&path).unwrap();
+
+        let loaded = Progress::load_from(&path);
+        fs::remove_file(&path).unwrap();
+        assert_eq!(loaded.history("intro"), progress.history("intro"));
+    }
+
+    #[test]
+    fn test_concurrent_updates_are_all_kept() {
+        let path =
+            std::env::temp_dir().join(format!("kiga_progress_updates_{}.json", std::process::id()));
+        std::thread::scope(|scope| {
+            for _ in 0..8 {
+                scope.spawn(|| {
+                    for at in 0..5 {
+                        update(&path, |progress| {
+                            progress.record("intro", attempt(at, Outcome::Failed))
+                        });
+                    }
+                });
+            }
+        });
+
+        let loaded = Progress::load_from(&path);
+        fs::remove_file(&path).unwrap();
+        assert_eq!(loaded.history("intro").unwrap().attempts, 40);
+    }
+
+    #[test]
+    fn test_format_timestamp() {
+        assert_eq!(format_timestamp(0), "1970-01-01 00:00:00 UTC");
+        assert_eq!(format_timestamp(1_709_210_096), "2024-02-29 12:34:56 UTC");
+    }
+}
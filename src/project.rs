@@ -1,6 +1,6 @@
+use crate::config;
 use glob::glob;
 use serde::{Deserialize, Serialize};
-use std::env;
 use std::error::Error;
 use std::path::PathBuf;
 use std::process::Command;
@@ -67,9 +67,9 @@ impl RustAnalyzerProject {
 
     /// Use `rustc` to determine the default toolchain
     pub fn get_sysroot_src(&mut self) -> Result<(), Box<dyn Error>> {
-        // check if RUST_SRC_PATH is set
-        if let Ok(path) = env::var("RUST_SRC_PATH") {
-            self.sysroot_src = path;
+        // check if rust_src_path is configured, e.g. with RUST_SRC_PATH
+        if let Some(path) = config::get().rust_src_path() {
+            self.sysroot_src = path.to_string();
             return Ok(());
         }
 
@@ -0,0 +1,147 @@
+use std::env;
+use std::io;
+use std::path::{Component, Path, PathBuf};
+
+// The file that marks the root of a kiga project
+pub const MANIFEST_NAME: &str = "info.toml";
+// Overrides the project root, like `--manifest`
+pub const ROOT_ENV: &str = "KIGA_ROOT";
+
+// Where the project lives and where kiga was started from
+#[derive(Debug, PartialEq)]
+pub struct Root {
+    // The directory containing the manifest
+    pub dir: PathBuf,
+    // The manifest, relative to `dir`
+    pub manifest: PathBuf,
+    // The working directory kiga was started in
+    pub invoked_from: PathBuf,
+}
+
+impl Root {
+    // Find the project root. An explicit manifest wins over `KIGA_ROOT`,
+    // which wins over walking up from the current directory
+    pub fn discover(manifest: Option<&Path>) -> Result<Root, String> {
+        let cwd =
+            env::current_dir().map_err(|e| format!("Can't read the current directory: {e}"))?;
+        let env_root = env::var_os(ROOT_ENV).map(PathBuf::from);
+        Self::discover_from(&cwd, manifest, env_root.as_deref())
+    }
+
+    fn discover_from(
+        cwd: &Path,
+        manifest: Option<&Path>,
+        env_root: Option<&Path>,
+    ) -> Result<Root, String> {
+        let explicit = manifest
+            .map(|path| (path, "--manifest"))
+            .or(env_root.map(|path| (path, ROOT_ENV)));
+
+        let manifest_path = match explicit {
+            Some((path, source)) => {
+                let path = cwd.join(path);
+                let path = if path.is_dir() {
+                    path.join(MANIFEST_NAME)
+                } else {
+                    path
+                };
+                if !path.is_file() {
+                    return Err(format!(
+                        "{source} points at {}, which doesn't exist",
+                        path.display()
+                    ));
+                }
+                path
+            }
+            None => cwd
+                .ancestors()
+                .map(|dir| dir.join(MANIFEST_NAME))
+                .find(|path| path.is_file())
+                .ok_or_else(|| {
+                    format!(
+                        "Couldn't find {MANIFEST_NAME} in {} or any parent directory",
+                        cwd.display()
+                    )
+                })?,
+        };
+
+        let manifest_path = manifest_path
+            .canonicalize()
+            .map_err(|e| format!("Can't resolve {}: {e}", manifest_path.display()))?;
+        let dir = manifest_path
+            .parent()
+            .map(Path::to_path_buf)
+            .unwrap_or_default();
+        let manifest = PathBuf::from(manifest_path.file_name().expect("the manifest is a file"));
+        Ok(Root {
+            dir,
+            manifest,
+            invoked_from: cwd.canonicalize().unwrap_or_else(|_| cwd.to_path_buf()),
+        })
+    }
+
+    // Make the root the working directory, so exercise paths, temporary
+    // binaries, `.kiga/` and `rust-project.json` all resolve against it
+    pub fn enter(&self) -> io::Result<()> {
+        env::set_current_dir(&self.dir)
+    }
+
+    // Turn a path given on the command line, relative to where kiga was
+    // started, into a path relative to the root when it lies inside it
+    pub fn resolve(&self, path: &Path) -> PathBuf {
+        let absolute = self.invoked_from.join(path);
+        match absolute.strip_prefix(&self.dir) {
+            Ok(relative) => relative.to_path_buf(),
+            Err(_) => absolute,
+        }
+    }
+}
+
+// Drop `.` components, so `./exercises/intro` matches `exercises/intro/intro.rs`
+pub fn normalize(path: &Path) -> PathBuf {
+    path.components()
+        .filter(|c| !matches!(c, Component::CurDir))
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn test_discover_walks_up_and_honours_overrides() {
+        let base = env::temp_dir().join(format!("kiga_root_{}", std::process::id()));
+        fs::create_dir_all(&base).unwrap();
+        let base = base.canonicalize().unwrap();
+        let nested = base.join("exercises").join("intro");
+        let other = base.join("other");
+        fs::create_dir_all(&nested).unwrap();
+        fs::create_dir_all(&other).unwrap();
+        fs::write(base.join(MANIFEST_NAME), "").unwrap();
+        fs::write(other.join("custom.toml"), "").unwrap();
+
+        let root = Root::discover_from(&nested, None, None).unwrap();
+        assert_eq!(root.dir, base);
+        assert_eq!(root.manifest, PathBuf::from(MANIFEST_NAME));
+        assert_eq!(
+            root.resolve(Path::new("intro.rs")),
+            PathBuf::from("exercises/intro/intro.rs")
+        );
+
+        let root = Root::discover_from(
+            &nested,
+            Some(Path::new("../../other/custom.toml")),
+            Some(&base),
+        )
+        .unwrap();
+        assert_eq!(root.dir, other);
+        assert_eq!(root.manifest, PathBuf::from("custom.toml"));
+
+        let root = Root::discover_from(&other, None, Some(&base)).unwrap();
+        assert_eq!(root.dir, base);
+
+        assert!(Root::discover_from(&other, Some(Path::new("missing.toml")), None).is_err());
+        fs::remove_dir_all(&base).unwrap();
+    }
+}
@@ -1,10 +1,15 @@
 use std::process::Command;
 use std::time::Duration;
 
+use crate::config;
 use crate::exercise::{Exercise, Mode};
+use crate::hooks;
 use crate::verify::test;
 use indicatif::ProgressBar;
 
+// The editor used when none is configured
+const DEFAULT_EDITOR: &str = "vi";
+
 // Invoke the rust compiler on the path of the given exercise,
 // and run the ensuing binary.
 // The verbose argument helps determine whether or not to show
@@ -18,17 +23,21 @@ pub fn run(exercise: &Exercise, verbose: bool) -> Result<(), ()> {
     Ok(())
 }
 
-// Resets the exercise by stashing the changes.
-pub fn reset(exercise: &Exercise) -> Result<(), ()> {
-    let command = Command::new("git")
-        .args(["stash", "--"])
+// Open the exercise in the configured editor and wait for it to close.
+// The setting may carry arguments, like `code --wait`
+pub fn edit(exercise: &Exercise) -> Result<(), String> {
+    let editor = config::get().editor().unwrap_or(DEFAULT_EDITOR);
+    let words = shell_words::split(editor)
+        .map_err(|e| format!("Can't parse the editor `{editor}`: {e}"))?;
+    let Some((program, args)) = words.split_first() else {
+        return Err("The configured editor is empty".to_string());
+    };
+    Command::new(program)
+        .args(args)
         .arg(&exercise.path)
-        .spawn();
-
-    match command {
-        Ok(_) => Ok(()),
-        Err(_) => Err(()),
-    }
+        .status()
+        .map(|_| ())
+        .map_err(|e| format!("Failed to run `{editor}`: {e}"))
 }
 
 // Invoke the rust compiler on the path of the given exercise
@@ -39,7 +48,7 @@ fn compile_and_run(exercise: &Exercise) -> Result<(), ()> {
     progress_bar.set_message(format!("Compiling {exercise}..."));
     progress_bar.enable_steady_tick(Duration::from_millis(100));
 
-    let compilation_result = exercise.compile();
+    let compilation_result = hooks::compile(exercise);
     let compilation = match compilation_result {
         Ok(compilation) => compilation,
         Err(output) => {
@@ -54,7 +63,7 @@ fn compile_and_run(exercise: &Exercise) -> Result<(), ()> {
     };
 
     progress_bar.set_message(format!("Running {exercise}..."));
-    let result = compilation.run();
+    let result = hooks::run_compiled(exercise, &compilation);
     progress_bar.finish_and_clear();
 
     match result {
@@ -0,0 +1,413 @@
+use regex::Regex;
+use rustyline::error::ReadlineError;
+use rustyline::{
+    Cmd, ConditionalEventHandler, DefaultEditor, Event, EventContext, EventHandler, KeyEvent,
+    RepeatCount,
+};
+use std::fs;
+use std::io::{self, IsTerminal};
+use std::process::Command;
+use std::sync::mpsc::{channel, Receiver, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+// Where the commands typed in watch mode are remembered between sessions
+const HISTORY_DIR: &str = ".kiga";
+const HISTORY_PATH: &str = ".kiga/watch_history";
+
+// The commands available in watch mode and what they do, shared by `help`
+// and the generated documentation
+pub const WATCH_COMMANDS: &[(&str, &str)] = &[
+    (
+        "hint",
+        "prints the next level of the current exercise's hint",
+    ),
+    ("run", "checks the current exercise again"),
+    ("next", "moves on to the next unfinished exercise"),
+    ("skip", "leaves the current exercise for later and moves on"),
+    ("list", "lists the exercises and which ones are done"),
+    (
+        "reset",
+        "resets the current exercise, keeping a snapshot to undo",
+    ),
+    (
+        "edit",
+        "opens the current exercise in the configured editor",
+    ),
+    (
+        "explain [code]",
+        "explains an error code, or the errors of the current exercise",
+    ),
+    (
+        "verbose on|off",
+        "shows or hides the output of passing exercises",
+    ),
+    ("clear", "clears the screen"),
+    ("quit", "quits watch mode"),
+    (
+        "!<cmd>",
+        "executes a command, like `!rustc --explain E0381`",
+    ),
+    ("help", "displays this help message"),
+];
+
+// Keys that run a command as soon as they are pressed on an empty line.
+// A command starting with one of them can still be typed after a `:`
+pub const SHORTCUTS: &[(char, ShellCommand)] = &[
+    ('h', ShellCommand::Hint),
+    ('n', ShellCommand::Next),
+    ('r', ShellCommand::Run),
+    ('c', ShellCommand::Clear),
+    ('q', ShellCommand::Quit),
+    ('?', ShellCommand::Help),
+];
+
+// A command typed in watch mode
+#[derive(Clone, Debug, PartialEq)]
+pub enum ShellCommand {
+    Hint,
+    Run,
+    Next,
+    Skip,
+    List,
+    Reset,
+    Edit,
+    // An error code like `E0381`, or None for the errors of the current exercise
+    Explain(Option<String>),
+    Verbose(bool),
+    Clear,
+    Quit,
+    // A program and its arguments
+    Exec(Vec<String>),
+    Help,
+}
+
+// Parse a line typed in watch mode, optionally starting with `:`. Blank
+// lines are no command
+pub fn parse(line: &str) -> Result<Option<ShellCommand>, String> {
+    let line = line.trim();
+    let line = line.strip_prefix(':').unwrap_or(line);
+    if let Some(command) = line.strip_prefix('!') {
+        let words =
+            shell_words::split(command).map_err(|e| format!("can't parse `{command}`: {e}"))?;
+        if words.is_empty() {
+            return Err("no command provided".to_string());
+        }
+        return Ok(Some(ShellCommand::Exec(words)));
+    }
+
+    let mut words = line.split_whitespace();
+    let Some(name) = words.next() else {
+        return Ok(None);
+    };
+    let argument = words.next();
+    if words.next().is_some() {
+        return Err(format!("too many arguments for `{name}`"));
+    }
+    let command = match (name, argument) {
+        ("verbose", Some("on")) => ShellCommand::Verbose(true),
+        ("verbose", Some("off")) => ShellCommand::Verbose(false),
+        ("verbose", _) => return Err("usage: verbose on|off".to_string()),
+        ("explain", code) => ShellCommand::Explain(code.map(error_code).transpose()?),
+        (_, Some(_)) if WATCH_COMMANDS.iter().any(|(c, _)| *c == name) => {
+            return Err(format!("`{name}` takes no arguments"))
+        }
+        ("hint", None) => ShellCommand::Hint,
+        ("run", None) => ShellCommand::Run,
+        ("next", None) => ShellCommand::Next,
+        ("skip", None) => ShellCommand::Skip,
+        ("list", None) => ShellCommand::List,
+        ("reset", None) => ShellCommand::Reset,
+        ("edit", None) => ShellCommand::Edit,
+        ("clear", None) => ShellCommand::Clear,
+        ("quit", None) => ShellCommand::Quit,
+        ("help", None) => ShellCommand::Help,
+        _ => return Err(format!("unknown command: {line}")),
+    };
+    Ok(Some(command))
+}
+
+// Accept `E0381`, `e0381` and `0381` for the same error code
+fn error_code(code: &str) -> Result<String, String> {
+    let digits = code.trim_start_matches(['E', 'e']);
+    if digits.len() == 4 && digits.chars().all(|c| c.is_ascii_digit()) {
+        Ok(format!("E{digits}"))
+    } else {
+        Err(format!("`{code}` isn't an error code like E0381"))
+    }
+}
+
+// The error codes in the output of rustc, each once and in order
+pub fn error_codes(output: &str) -> Vec<String> {
+    let pattern = Regex::new(r"error\[(E\d{4})\]").unwrap();
+    let output = console::strip_ansi_codes(output);
+    let mut codes: Vec<String> = Vec::new();
+    for captures in pattern.captures_iter(&output) {
+        if !codes.iter().any(|c| *c == captures[1]) {
+            codes.push(captures[1].to_string());
+        }
+    }
+    codes
+}
+
+// How a command is typed at the prompt. With shortcuts, one starting with
+// the key of another command's shortcut needs a `:` in front
+fn spelled(command: &str, shortcuts: bool) -> String {
+    let shadowed = SHORTCUTS.iter().any(|(key, shortcut)| {
+        command.starts_with(*key) && parse(command).ok().flatten().as_ref() != Some(shortcut)
+    });
+    if shortcuts && shadowed {
+        format!(":{command}")
+    } else {
+        command.to_string()
+    }
+}
+
+pub fn print_help() {
+    let shortcuts = io::stdin().is_terminal();
+    println!("Commands available to you in watch mode:");
+    for (command, description) in WATCH_COMMANDS {
+        let command = spelled(command, shortcuts);
+        println!("  {command:<14} - {description}");
+    }
+    println!();
+    let keys: Vec<String> = SHORTCUTS.iter().map(|(key, _)| key.to_string()).collect();
+    println!(
+        "On an empty line, {} act as soon as you press them. Type `:` first",
+        keys.join(", ")
+    );
+    println!("to spell out a command starting with one of them, like `:reset`.");
+    println!();
+    println!("Watch mode automatically re-evaluates the current exercise");
+    println!("when you edit a file's contents.")
+}
+
+// Show the long explanation of a compiler error
+pub fn explain(code: &str) {
+    if let Err(e) = Command::new("rustc").args(["--explain", code]).status() {
+        println!("failed to run `rustc --explain {code}`: {e}");
+    }
+}
+
+// Run a command typed after `!`
+pub fn exec(words: &[String]) {
+    let Some((program, args)) = words.split_first() else {
+        return;
+    };
+    if let Err(e) = Command::new(program).args(args).status() {
+        println!(
+            "failed to execute command `{}`: {e}",
+            shell_words::join(words)
+        );
+    }
+}
+
+// Reads commands on a thread of its own, with line editing and history.
+// After sending a command the thread waits for `done`, so a command that
+// takes over the terminal, like `edit`, doesn't fight with the prompt
+pub struct Commands {
+    commands: Receiver<ShellCommand>,
+    done: Sender<()>,
+    terminal: Terminal,
+}
+
+impl Commands {
+    pub fn spawn() -> Self {
+        let (command_tx, commands) = channel();
+        let (done, done_rx) = channel();
+        // Saved before the prompt puts the terminal into raw mode
+        let terminal = Terminal::save();
+        thread::spawn(move || read_commands(command_tx, done_rx));
+        Commands {
+            commands,
+            done,
+            terminal,
+        }
+    }
+
+    // The next command typed, if there is one
+    pub fn try_next(&self) -> Option<ShellCommand> {
+        self.commands.try_recv().ok()
+    }
+
+    // Let the prompt come back after handling a command
+    pub fn done(&self) {
+        let _ = self.done.send(());
+    }
+}
+
+impl Drop for Commands {
+    // Watch mode can end while the prompt waits for a line, which would leave
+    // the terminal in raw mode
+    fn drop(&mut self) {
+        self.terminal.restore();
+    }
+}
+
+// Runs its command when its key is pressed on an empty line, and otherwise
+// lets the key be typed
+struct Shortcut {
+    command: ShellCommand,
+    pressed: Arc<Mutex<Option<ShellCommand>>>,
+}
+
+impl ConditionalEventHandler for Shortcut {
+    fn handle(&self, _: &Event, _: RepeatCount, _: bool, ctx: &EventContext) -> Option<Cmd> {
+        if !ctx.line().is_empty() {
+            return None;
+        }
+        *self.pressed.lock().unwrap() = Some(self.command.clone());
+        Some(Cmd::AcceptLine)
+    }
+}
+
+fn read_commands(commands: Sender<ShellCommand>, done: Receiver<()>) {
+    let mut editor = match DefaultEditor::new() {
+        Ok(editor) => editor,
+        Err(e) => return println!("error reading commands: {e}"),
+    };
+    let _ = editor.load_history(HISTORY_PATH);
+    let pressed = Arc::new(Mutex::new(None));
+    for (key, command) in SHORTCUTS {
+        let shortcut = Shortcut {
+            command: command.clone(),
+            pressed: Arc::clone(&pressed),
+        };
+        editor.bind_sequence(
+            KeyEvent::from(*key),
+            EventHandler::Conditional(Box::new(shortcut)),
+        );
+    }
+    loop {
+        let line = editor.readline("");
+        let shortcut = pressed.lock().unwrap().take();
+        let command = match (line, shortcut) {
+            (Ok(_), Some(command)) => command,
+            (Ok(line), None) => {
+                if !line.trim().is_empty() {
+                    let _ = editor.add_history_entry(line.as_str());
+                    let _ = fs::create_dir_all(HISTORY_DIR);
+                    let _ = editor.save_history(HISTORY_PATH);
+                }
+                match parse(&line) {
+                    Ok(Some(command)) => command,
+                    Ok(None) => continue,
+                    Err(message) => {
+                        println!("{message}");
+                        continue;
+                    }
+                }
+            }
+            (Err(ReadlineError::Interrupted), _) => ShellCommand::Quit,
+            (Err(ReadlineError::Eof), _) if io::stdin().is_terminal() => ShellCommand::Quit,
+            // Piped input ran out: keep watching, without commands
+            (Err(ReadlineError::Eof), _) => return,
+            (Err(e), _) => return println!("error reading command: {e}"),
+        };
+        let quit = command == ShellCommand::Quit;
+        if commands.send(command).is_err() {
+            return;
+        }
+        if quit {
+            return println!("Bye!");
+        }
+        if done.recv().is_err() {
+            return;
+        }
+    }
+}
+
+// The terminal settings of stdin, to put back when watch mode ends
+#[cfg(unix)]
+struct Terminal(Option<libc::termios>);
+
+#[cfg(unix)]
+impl Terminal {
+    fn save() -> Self {
+        // SAFETY: tcgetattr only writes into the termios it is given
+        unsafe {
+            let mut termios: libc::termios = std::mem::zeroed();
+            let saved = libc::tcgetattr(libc::STDIN_FILENO, &mut termios) == 0;
+            Terminal(saved.then_some(termios))
+        }
+    }
+
+    fn restore(&self) {
+        if let Some(termios) = &self.0 {
+            // SAFETY: the termios was filled in by tcgetattr
+            unsafe {
+                libc::tcsetattr(libc::STDIN_FILENO, libc::TCSANOW, termios);
+            }
+        }
+    }
+}
+
+#[cfg(not(unix))]
+struct Terminal;
+
+#[cfg(not(unix))]
+impl Terminal {
+    fn save() -> Self {
+        Terminal
+    }
+
+    fn restore(&self) {}
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_parse_commands() {
+        assert_eq!(parse("  "), Ok(None));
+        assert_eq!(parse("next"), Ok(Some(ShellCommand::Next)));
+        assert_eq!(parse(":reset"), Ok(Some(ShellCommand::Reset)));
+        assert_eq!(
+            parse(" verbose off "),
+            Ok(Some(ShellCommand::Verbose(false)))
+        );
+        assert!(parse("verbose").is_err());
+        assert!(parse("run now").is_err());
+        assert!(parse("jump").is_err());
+        assert_eq!(parse("explain"), Ok(Some(ShellCommand::Explain(None))));
+        assert_eq!(
+            parse("explain e0381"),
+            Ok(Some(ShellCommand::Explain(Some("E0381".to_string()))))
+        );
+        assert!(parse("explain borrow").is_err());
+    }
+
+    #[test]
+    fn test_shadowed_commands_are_spelled_with_a_colon() {
+        assert_eq!(spelled("reset", true), ":reset");
+        assert_eq!(spelled("help", true), ":help");
+        assert_eq!(spelled("hint", true), "hint");
+        assert_eq!(spelled("run", true), "run");
+        assert_eq!(spelled("explain [code]", true), "explain [code]");
+        assert_eq!(spelled("reset", false), "reset");
+    }
+
+    #[test]
+    fn test_parse_exec_splits_shell_words() {
+        assert_eq!(
+            parse(r#"!git commit -m "a message" 'it''s'"#),
+            Ok(Some(ShellCommand::Exec(
+                ["git", "commit", "-m", "a message", "its"]
+                    .map(String::from)
+                    .to_vec()
+            )))
+        );
+        assert!(parse("!").is_err());
+        assert!(parse("!echo \"open").is_err());
+    }
+
+    #[test]
+    fn test_error_codes() {
+        let output = "\x1b[1m\x1b[38;5;9merror[E0381]\x1b[0m: used binding\n\
+                      error[E0308]: mismatched types\n\
+                      error[E0381]: used binding\n\
+                      error: aborting due to 3 previous errors";
+        assert_eq!(error_codes(output), ["E0381", "E0308"]);
+    }
+}
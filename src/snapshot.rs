@@ -0,0 +1,231 @@
+use crate::exercise::Exercise;
+use crate::progress::format_timestamp;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+// Where the versions replaced by `reset` are kept, one directory per exercise
+const SNAPSHOT_DIR: &str = ".kiga/snapshots";
+
+// A saved version of an exercise file
+#[derive(Debug, PartialEq)]
+pub struct Snapshot {
+    pub path: PathBuf,
+    // When it was taken, in milliseconds since the Unix epoch
+    pub taken_ms: u64,
+}
+
+impl Snapshot {
+    pub fn describe(&self) -> String {
+        let lines = fs::read_to_string(&self.path).map_or(0, |s| s.lines().count());
+        format!(
+            "{}  {lines:>4} lines  {}",
+            format_timestamp(self.taken_ms / 1_000),
+            self.path.display()
+        )
+    }
+}
+
+fn dir(exercise: &Exercise) -> PathBuf {
+    // Namespaced names like `concurrency/intro` stay one directory deep
+    Path::new(SNAPSHOT_DIR).join(exercise.name.replace('/', "__"))
+}
+
+// The snapshots of an exercise, oldest first
+pub fn list(exercise: &Exercise) -> Vec<Snapshot> {
+    let Ok(entries) = fs::read_dir(dir(exercise)) else {
+        return Vec::new();
+    };
+    let mut snapshots: Vec<Snapshot> = entries
+        .filter_map(|entry| {
+            let path = entry.ok()?.path();
+            let taken_ms = path.file_stem()?.to_str()?.parse().ok()?;
+            Some(Snapshot { path, taken_ms })
+        })
+        .collect();
+    snapshots.sort_by_key(|s| s.taken_ms);
+    snapshots
+}
+
+fn save(exercise: &Exercise, content: &str) -> Result<Snapshot, String> {
+    let dir = dir(exercise);
+    fs::create_dir_all(&dir).map_err(|e| format!("Can't create {}: {e}", dir.display()))?;
+    let mut taken_ms = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_millis() as u64);
+    // Never overwrite a snapshot taken in the same millisecond
+    let mut path = dir.join(format!("{taken_ms}.rs"));
+    while path.exists() {
+        taken_ms += 1;
+        path = dir.join(format!("{taken_ms}.rs"));
+    }
+    fs::write(&path, content).map_err(|e| format!("Can't write {}: {e}", path.display()))?;
+    Ok(Snapshot { path, taken_ms })
+}
+
+// The content an exercise starts out with: its `template` from info.toml,
+// or else the version it was added to git with. Later commits, like ones
+// made by an on_success hook, don't change what it started out as
+pub fn original(exercise: &Exercise) -> Result<String, String> {
+    if let Some(template) = &exercise.template {
+        return fs::read_to_string(template)
+            .map_err(|e| format!("Can't read the template {}: {e}", template.display()));
+    }
+    committed_original(&exercise.path).ok_or_else(|| {
+        format!(
+            "{exercise} has no `template` in info.toml and isn't committed to git, \
+             so there's nothing to reset it to"
+        )
+    })
+}
+
+// The file as of the first commit that added it
+fn committed_original(path: &Path) -> Option<String> {
+    // Run git next to the file, so the paths it takes are just the file name
+    let dir = path.parent().filter(|dir| !dir.as_os_str().is_empty());
+    let dir = dir.unwrap_or(Path::new("."));
+    let name = path.file_name()?.to_str()?;
+    let commits = git(
+        dir,
+        &[
+            "log",
+            "--reverse",
+            "--diff-filter=A",
+            "--format=%H",
+            "--",
+            name,
+        ],
+    )?;
+    let first = commits.lines().next()?;
+    git(dir, &["show", &format!("{first}:./{name}")])
+}
+
+// The output of a git command that succeeded
+fn git(dir: &Path, args: &[&str]) -> Option<String> {
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(dir)
+        .args(args)
+        .output()
+        .ok()?;
+    output
+        .status
+        .success()
+        .then(|| String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+// Put the original content back into an exercise, saving the current
+// version first. Returns the snapshot, if the file had something to save
+pub fn reset(exercise: &Exercise) -> Result<Option<Snapshot>, String> {
+    let original = original(exercise)?;
+    let current = fs::read_to_string(&exercise.path).ok();
+    if current.as_deref() == Some(original.as_str()) {
+        return Ok(None);
+    }
+    let snapshot = current.map(|c| save(exercise, &c)).transpose()?;
+    write(exercise, &original)?;
+    Ok(snapshot)
+}
+
+// What a finished reset did, for the user
+pub fn reset_message(exercise: &Exercise, snapshot: &Option<Snapshot>) -> String {
+    match snapshot {
+        Some(_) => format!(
+            "Reset {exercise}. Your version was saved, `kiga undo {}` brings it back",
+            exercise.name
+        ),
+        None => format!("{exercise} is already as it started out"),
+    }
+}
+
+// Bring back the newest snapshot of an exercise, which is then removed.
+// The version it replaces is saved in turn, unless it's the original
+pub fn undo(exercise: &Exercise) -> Result<Snapshot, String> {
+    let latest = list(exercise)
+        .pop()
+        .ok_or_else(|| format!("There are no snapshots of {exercise} to go back to"))?;
+    let content = fs::read_to_string(&latest.path)
+        .map_err(|e| format!("Can't read {}: {e}", latest.path.display()))?;
+    if let Ok(current) = fs::read_to_string(&exercise.path) {
+        if current != content && original(exercise).ok().as_ref() != Some(&current) {
+            save(exercise, &current)?;
+        }
+    }
+    write(exercise, &content)?;
+    fs::remove_file(&latest.path)
+        .map_err(|e| format!("Can't remove {}: {e}", latest.path.display()))?;
+    Ok(latest)
+}
+
+fn write(exercise: &Exercise, content: &str) -> Result<(), String> {
+    fs::write(&exercise.path, content).map_err(|e| format!("Can't write {exercise}: {e}"))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_reset_and_undo() {
+        let dir = std::env::temp_dir().join(format!("kiga_snapshot_{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let template = dir.join("template.rs");
+        fs::write(&template, "// start here\n").unwrap();
+        let mut exercise = Exercise::default();
+        exercise.name = format!("snapshot_test_{}", std::process::id());
+        exercise.path = dir.join("exercise.rs");
+        exercise.template = Some(template);
+
+        fs::write(&exercise.path, "// my attempt\n").unwrap();
+        let snapshot = reset(&exercise).unwrap().unwrap();
+        assert_eq!(
+            fs::read_to_string(&exercise.path).unwrap(),
+            "// start here\n"
+        );
+        assert_eq!(list(&exercise), [snapshot]);
+        // Resetting an untouched exercise saves nothing
+        assert_eq!(reset(&exercise).unwrap(), None);
+
+        undo(&exercise).unwrap();
+        assert_eq!(
+            fs::read_to_string(&exercise.path).unwrap(),
+            "// my attempt\n"
+        );
+        assert!(list(&exercise).is_empty());
+        assert!(undo(&exercise).is_err());
+
+        fs::remove_dir_all(&dir).unwrap();
+        let _ = fs::remove_dir_all(super::dir(&exercise));
+    }
+
+    #[test]
+    fn test_original_is_the_version_first_committed() {
+        let dir = std::env::temp_dir().join(format!("kiga_original_{}", std::process::id()));
+        fs::create_dir_all(dir.join("exercises")).unwrap();
+        let git = |args: &[&str]| {
+            let status = Command::new("git")
+                .arg("-C")
+                .arg(&dir)
+                .args(["-c", "user.name=kiga", "-c", "user.email=kiga@example.com"])
+                .args(["-c", "commit.gpgsign=false"])
+                .args(args)
+                .status()
+                .unwrap();
+            assert!(status.success());
+        };
+        let mut exercise = Exercise::named("intro");
+        exercise.path = dir.join("exercises/intro.rs");
+        fs::write(&exercise.path, "// start here\n").unwrap();
+        git(&["init", "-q"]);
+        assert!(original(&exercise).is_err());
+
+        git(&["add", "."]);
+        git(&["commit", "-qm", "Add intro"]);
+        fs::write(&exercise.path, "// solved\n").unwrap();
+        git(&["commit", "-qam", "Solve intro"]);
+        assert_eq!(original(&exercise).unwrap(), "// start here\n");
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,149 @@
+use crate::exercise::Exercise;
+use console::style;
+use similar::{ChangeTag, TextDiff};
+use std::fs;
+use std::io::{self, IsTerminal, Write};
+use std::path::Path;
+
+// Lines of unchanged code shown around each difference
+const DIFF_CONTEXT: usize = 3;
+
+fn path(exercise: &Exercise) -> Result<&Path, String> {
+    exercise
+        .solution
+        .as_deref()
+        .ok_or_else(|| format!("{} has no solution in info.toml", exercise.name))
+}
+
+fn read(path: &Path) -> Result<String, String> {
+    fs::read_to_string(path).map_err(|e| format!("Can't read {}: {e}", path.display()))
+}
+
+// Ask before spoiling an exercise. Without a terminal to ask on, only
+// `--yes` shows the solution
+fn confirm(exercise: &Exercise, terminal: bool) -> bool {
+    if !terminal {
+        println!("Pass --yes to show the solution of {}", exercise.name);
+        return false;
+    }
+    print!(
+        "Show the solution of {}? You'll learn more by finishing it first. [y/N] ",
+        exercise.name
+    );
+    let _ = io::stdout().flush();
+    let mut answer = String::new();
+    if io::stdin().read_line(&mut answer).is_err() {
+        return false;
+    }
+    matches!(answer.trim().to_lowercase().as_str(), "y" | "yes")
+}
+
+// Print the stored solution of an exercise, once the user confirmed
+pub fn print(exercise: &Exercise, yes: bool) -> Result<(), String> {
+    if let Some((path, solution)) = reveal(exercise, yes, io::stdin().is_terminal())? {
+        println!("{}", style(path.display()).bold());
+        println!("{solution}");
+    }
+    Ok(())
+}
+
+// The path and content of an exercise's solution, or None if the user
+// didn't want to see it
+fn reveal(
+    exercise: &Exercise,
+    yes: bool,
+    terminal: bool,
+) -> Result<Option<(&Path, String)>, String> {
+    let path = path(exercise)?;
+    let solution = read(path)?;
+    Ok((yes || confirm(exercise, terminal)).then_some((path, solution)))
+}
+
+// Show how the working file differs from the solution, as a unified diff
+pub fn diff(exercise: &Exercise) -> Result<(), String> {
+    for line in diff_lines(exercise)? {
+        println!("{line}");
+    }
+    Ok(())
+}
+
+// The lines `diff` prints
+fn diff_lines(exercise: &Exercise) -> Result<Vec<String>, String> {
+    let path = path(exercise)?;
+    let solution = read(path)?;
+    let current = read(&exercise.path)?;
+    if current == solution {
+        return Ok(vec![format!("{exercise} matches the solution")]);
+    }
+    let mut lines = vec![
+        style(format!("--- {exercise}")).bold().to_string(),
+        style(format!("+++ {}", path.display())).bold().to_string(),
+    ];
+    let diff = TextDiff::from_lines(&current, &solution);
+    for hunk in diff
+        .unified_diff()
+        .context_radius(DIFF_CONTEXT)
+        .iter_hunks()
+    {
+        lines.push(style(hunk.header()).cyan().to_string());
+        for change in hunk.iter_changes() {
+            let line = format!("{}{}", change.tag(), change.value());
+            let line = line.trim_end_matches('\n');
+            lines.push(match change.tag() {
+                ChangeTag::Delete => style(line).red().to_string(),
+                ChangeTag::Insert => style(line).green().to_string(),
+                ChangeTag::Equal => line.to_string(),
+            });
+        }
+    }
+    Ok(lines)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_solutions_are_shown_and_diffed() {
+        let dir = std::env::temp_dir().join(format!("kiga_solution_{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let solution = dir.join("solution.rs");
+        fs::write(&solution, "fn main() {\n    println!(\"done\");\n}\n").unwrap();
+        let mut exercise = Exercise::named("solution_test");
+        exercise.path = dir.join("exercise.rs");
+        fs::write(&exercise.path, "fn main() {\n    todo!();\n}\n").unwrap();
+
+        assert_eq!(
+            reveal(&exercise, false, false).unwrap_err(),
+            "solution_test has no solution in info.toml"
+        );
+        exercise.solution = Some(solution.clone());
+        // Without a terminal to ask on, only `--yes` shows it
+        assert_eq!(reveal(&exercise, false, false), Ok(None));
+        let (path, content) = reveal(&exercise, true, false).unwrap().unwrap();
+        assert_eq!(path, solution);
+        assert!(content.contains("done"));
+
+        let lines: Vec<String> = diff_lines(&exercise)
+            .unwrap()
+            .iter()
+            .map(|line| console::strip_ansi_codes(line).into_owned())
+            .collect();
+        assert_eq!(
+            lines[2..],
+            [
+                "@@ -1,3 +1,3 @@",
+                " fn main() {",
+                "-    todo!();",
+                "+    println!(\"done\");",
+                " }"
+            ]
+        );
+        fs::copy(&solution, &exercise.path).unwrap();
+        assert_eq!(
+            diff_lines(&exercise).unwrap(),
+            [format!("{exercise} matches the solution")]
+        );
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
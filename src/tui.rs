@@ -0,0 +1,548 @@
+use crate::alert;
+use crate::category::{self, Categories};
+use crate::config;
+use crate::exercise::Exercise;
+use crate::graph;
+use crate::hint;
+use crate::hooks;
+use crate::job::{self, Job};
+use crate::progress::{self, Outcome, Progress};
+use crate::run;
+use crate::snapshot;
+use crate::verify;
+use crate::watcher::{Change, FileWatcher};
+use crate::{catalogue, Follow, WatchStatus};
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEventKind, KeyModifiers};
+use ratatui::crossterm::execute;
+use ratatui::crossterm::terminal::{
+    disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen,
+};
+use ratatui::layout::{Constraint, Layout};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, List, ListItem, ListState, Paragraph, Wrap};
+use ratatui::{DefaultTerminal, Frame};
+use std::collections::VecDeque;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{channel, Receiver, Sender};
+use std::thread::{self, Scope};
+use std::time::Duration;
+
+// The key bindings, shown at the bottom of the screen
+const KEYS: &str =
+    "r run  n next  h hint  c clear  R reset  e edit  ↑↓ select  PgUp/PgDn scroll  q quit";
+// How many lines PgUp/PgDn scroll the output
+const SCROLL_STEP: u16 = 10;
+
+// Watch the exercises in a full-screen terminal UI until the user quits or,
+// when following the catalogue, all exercises are done.
+// The terminal is restored before returning
+pub fn watch(
+    exercises: &[&Exercise],
+    categories: &Categories,
+    follow: Follow,
+    verbose: bool,
+    success_hints: bool,
+    watcher: &FileWatcher,
+    notice: Option<String>,
+) -> notify::Result<WatchStatus> {
+    let mut terminal = ratatui::init();
+    hooks::set_quiet(true);
+    // Checks run in the background, so the UI stays responsive and a newer
+    // change can cancel them
+    let status = thread::scope(|scope| {
+        let mut app = App::new(exercises, categories, follow, verbose, success_hints, scope);
+        let status = app.run(&mut terminal, watcher, notice);
+        app.cancel();
+        status
+    });
+    hooks::set_quiet(false);
+    ratatui::restore();
+    Ok(status?)
+}
+
+// What a background check tells the UI
+enum Report {
+    Checking(usize),
+    Checked(usize, Outcome, String),
+}
+
+struct App<'a, 's> {
+    exercises: &'a [&'a Exercise],
+    categories: &'a Categories,
+    follow: Follow,
+    // Whether the output of passing tests is shown
+    verbose: bool,
+    scope: &'s Scope<'s, 'a>,
+    // The running check, which returns whether to select the first
+    // unfinished exercise once it's done
+    job: Option<Job<'s, bool>>,
+    // The checks waiting for the running one, as `start` takes them
+    queued: VecDeque<(Vec<usize>, bool, bool)>,
+    reports: Receiver<Report>,
+    reporter: Sender<Report>,
+    // The outcome of the last check of each exercise
+    outcomes: Vec<Outcome>,
+    list: ListState,
+    // The output of the last check of each exercise
+    outputs: Vec<Option<String>>,
+    scroll: u16,
+    show_hint: bool,
+    // How many levels of each exercise's hint have been revealed
+    hints_seen: Vec<usize>,
+    // The rendered levels of each exercise's hint, once shown
+    hints: Vec<Option<Vec<String>>>,
+    status: String,
+    // Whether finished checks are signalled as the notify.* settings say,
+    // which the first check isn't
+    alert: bool,
+}
+
+impl<'a, 's> App<'a, 's> {
+    fn new(
+        exercises: &'a [&'a Exercise],
+        categories: &'a Categories,
+        follow: Follow,
+        verbose: bool,
+        show_hint: bool,
+        scope: &'s Scope<'s, 'a>,
+    ) -> Self {
+        // Exercises that need a build to tell count as pending until checked
+        let outcomes: Vec<Outcome> = exercises
+            .iter()
+            .map(|e| {
+                if e.known_done() {
+                    Outcome::Passed
+                } else {
+                    Outcome::Pending
+                }
+            })
+            .collect();
+        let (reporter, reports) = channel();
+        let progress = Progress::load();
+        let mut app = App {
+            exercises,
+            categories,
+            follow,
+            verbose,
+            scope,
+            job: None,
+            queued: VecDeque::new(),
+            reports,
+            reporter,
+            outcomes,
+            list: ListState::default(),
+            outputs: vec![None; exercises.len()],
+            scroll: 0,
+            show_hint,
+            hints_seen: exercises
+                .iter()
+                .map(|e| progress.hints_seen(&e.name))
+                .collect(),
+            hints: vec![None; exercises.len()],
+            status: String::new(),
+            alert: false,
+        };
+        app.list.select(Some(app.first_pending().unwrap_or(0)));
+        app
+    }
+
+    fn run(
+        &mut self,
+        terminal: &mut DefaultTerminal,
+        watcher: &FileWatcher,
+        notice: Option<String>,
+    ) -> io::Result<WatchStatus> {
+        match self.follow {
+            Follow::Catalogue => self.check_pending(None),
+            Follow::Changed => self.status = "Save an exercise to run it".to_string(),
+            Follow::Pinned => self.check_all(),
+        }
+        self.alert = true;
+        if let Some(note) = notice.or_else(|| watcher.polling_note()) {
+            self.status = note;
+        }
+        loop {
+            self.receive_reports();
+            if self.job.as_ref().is_some_and(Job::is_finished) {
+                self.finish_job();
+            }
+            if self.job.is_none() {
+                if let Some((order, stop_early, select_pending)) = self.queued.pop_front() {
+                    self.start(order, stop_early, select_pending);
+                }
+            }
+            if self.follow == Follow::Catalogue
+                && self.job.is_none()
+                && self.first_pending().is_none()
+            {
+                return Ok(WatchStatus::Finished);
+            }
+            // A hint shown without pressing h, like the one opened for
+            // success_hints, is revealed all the same
+            if self.show_hint && self.hints_seen.get(self.selected()) == Some(&0) {
+                self.reveal_hint();
+            }
+            terminal.draw(|frame| self.draw(frame))?;
+
+            let changes = watcher.changes(Duration::ZERO).unwrap_or_else(|e| {
+                self.status = format!("Watch error: {e}");
+                Vec::new()
+            });
+            for change in changes {
+                match change {
+                    // Keep the exercises loaded before while info.toml has errors
+                    Change::Catalogue => match catalogue::load(watcher.manifest()) {
+                        Ok((list, _)) => return Ok(WatchStatus::Reload(Box::new(list))),
+                        Err(diagnostics) => {
+                            let errors = diagnostics.iter().filter(|d| d.is_error()).count();
+                            self.status =
+                                format!("info.toml has {errors} error(s), run `kiga check-config`");
+                        }
+                    },
+                    Change::Source(path) => {
+                        // The change may be to a hint file
+                        self.hints.fill(None);
+                        let changed = self.changed(&path);
+                        match (self.follow, changed) {
+                            (Follow::Catalogue, _) => self.check_pending(changed),
+                            (_, Some(i)) => self.check(i),
+                            (Follow::Pinned, None) => self.check_all(),
+                            (Follow::Changed, None) => {}
+                        }
+                    }
+                }
+            }
+
+            // A SIGINT from outside, since Ctrl-C itself arrives as a key
+            if job::interrupted() {
+                return Ok(WatchStatus::Unfinished);
+            }
+            if !event::poll(Duration::from_millis(100))? {
+                continue;
+            }
+            let Event::Key(key) = event::read()? else {
+                continue;
+            };
+            if key.kind != KeyEventKind::Press {
+                continue;
+            }
+            if key.code == KeyCode::Char('c') && key.modifiers.contains(KeyModifiers::CONTROL) {
+                return Ok(WatchStatus::Unfinished);
+            }
+            match key.code {
+                KeyCode::Char('q') | KeyCode::Esc => return Ok(WatchStatus::Unfinished),
+                KeyCode::Up | KeyCode::Char('k') => self.move_selection(-1),
+                KeyCode::Down | KeyCode::Char('j') => self.move_selection(1),
+                KeyCode::PageUp => self.scroll = self.scroll.saturating_sub(SCROLL_STEP),
+                KeyCode::PageDown => self.scroll = self.scroll.saturating_add(SCROLL_STEP),
+                KeyCode::Char('h') => {
+                    // Each time the panel opens, it reveals one more level
+                    self.show_hint = !self.show_hint;
+                    if self.show_hint {
+                        self.reveal_hint();
+                    }
+                }
+                KeyCode::Char('c') => {
+                    let selected = self.selected();
+                    if let Some(output) = self.outputs.get_mut(selected) {
+                        *output = None;
+                    }
+                    self.scroll = 0;
+                }
+                KeyCode::Char('r') if self.current().is_some() => self.check(self.selected()),
+                KeyCode::Char('n') => {
+                    if let Some(next) = self.first_pending() {
+                        self.check(next);
+                    }
+                }
+                KeyCode::Char('R') => {
+                    let Some(exercise) = self.current() else {
+                        continue;
+                    };
+                    self.status = match snapshot::reset(exercise) {
+                        Ok(snapshot) => snapshot::reset_message(exercise, &snapshot),
+                        Err(message) => message,
+                    };
+                }
+                KeyCode::Char('e') => self.edit(terminal)?,
+                _ => {}
+            }
+        }
+    }
+
+    // The watched exercise stored at `path`. When following the catalogue,
+    // only if its requirements are met
+    fn changed(&self, path: &Path) -> Option<usize> {
+        self.exercises.iter().position(|e| {
+            path.ends_with(&e.path)
+                && (self.follow != Follow::Catalogue || graph::requirements_met(e, self.exercises))
+        })
+    }
+
+    // Check every watched exercise, then select the first that isn't done
+    fn check_all(&mut self) {
+        self.start((0..self.exercises.len()).collect(), false, true);
+    }
+
+    // Check `first`, then every other unfinished exercise in order,
+    // stopping at the first one that isn't done, like the line mode does
+    fn check_pending(&mut self, first: Option<usize>) {
+        let rest = (0..self.exercises.len())
+            .filter(|&i| Some(i) != first && self.outcomes[i] != Outcome::Passed);
+        let order: Vec<usize> = first.into_iter().chain(rest).collect();
+        self.start(order, true, false);
+    }
+
+    fn check(&mut self, i: usize) {
+        self.start(vec![i], false, false);
+    }
+
+    // Check the exercises in `order` in the background. A running check of
+    // any of them is cancelled, after any other running check it's queued
+    fn start(&mut self, order: Vec<usize>, stop_early: bool, select_pending: bool) {
+        let targets: Vec<PathBuf> = order
+            .iter()
+            .map(|&i| self.exercises[i].path.clone())
+            .collect();
+        if self
+            .job
+            .as_ref()
+            .is_some_and(|running| running.overlaps(&targets))
+        {
+            self.cancel();
+        }
+        // Queued checks of only these exercises aren't needed anymore
+        self.queued
+            .retain(|(waiting, _, _)| !waiting.iter().all(|i| order.contains(i)));
+        if self.job.is_some() || !self.queued.is_empty() {
+            self.queued.push_back((order, stop_early, select_pending));
+            return;
+        }
+        let exercises = self.exercises;
+        let reporter = self.reporter.clone();
+        let alert = self.alert;
+        let verbose = self.verbose;
+        self.job = Some(Job::spawn(self.scope, targets, move || {
+            let mut checked = Vec::new();
+            for i in order {
+                let _ = reporter.send(Report::Checking(i));
+                let (outcome, output) = verify::check(exercises[i], verbose);
+                if job::cancelled() {
+                    return select_pending;
+                }
+                let _ = reporter.send(Report::Checked(i, outcome, output));
+                checked.push((exercises[i], outcome));
+                if stop_early && outcome != Outcome::Passed {
+                    break;
+                }
+            }
+            // Only passes that stop early move on to the next exercise
+            if let Some(event) = alert::event(&checked, stop_early).filter(|_| alert) {
+                alert::notify(&event);
+            }
+            select_pending
+        }));
+    }
+
+    // Join the finished check and take in what it found
+    fn finish_job(&mut self) {
+        if let Some(job) = self.job.take() {
+            let select_pending = job.join();
+            self.receive_reports();
+            if let Some(i) = self.first_pending().filter(|_| select_pending) {
+                self.list.select(Some(i));
+            }
+        }
+    }
+
+    fn cancel(&mut self) {
+        if let Some(job) = self.job.take() {
+            job.cancel();
+        }
+    }
+
+    fn receive_reports(&mut self) {
+        while let Ok(report) = self.reports.try_recv() {
+            match report {
+                Report::Checking(i) => {
+                    self.list.select(Some(i));
+                    self.status = format!("Checking {}...", self.exercises[i]);
+                }
+                Report::Checked(i, outcome, output) => {
+                    let exercise = self.exercises[i];
+                    self.outcomes[i] = outcome;
+                    self.outputs[i] = Some(output);
+                    self.scroll = 0;
+                    self.status = match outcome {
+                        Outcome::Passed => format!("{exercise} is done!"),
+                        Outcome::Pending => format!("{exercise} isn't done yet"),
+                        Outcome::Failed => format!("{exercise} failed"),
+                    };
+                }
+            }
+        }
+    }
+
+    // Leave the TUI while the configured editor edits the selected exercise
+    fn edit(&mut self, terminal: &mut DefaultTerminal) -> io::Result<()> {
+        let Some(exercise) = self.current() else {
+            return Ok(());
+        };
+        disable_raw_mode()?;
+        execute!(io::stdout(), LeaveAlternateScreen)?;
+        let result = run::edit(exercise);
+        enable_raw_mode()?;
+        execute!(io::stdout(), EnterAlternateScreen)?;
+        terminal.clear()?;
+
+        if let Err(message) = result {
+            self.status = message;
+        }
+        Ok(())
+    }
+
+    fn selected(&self) -> usize {
+        self.list.selected().unwrap_or(0)
+    }
+
+    // The levels of an exercise's hint, rendered the first time they're needed
+    fn hint(&mut self, i: usize) -> &[String] {
+        let exercise = self.exercises[i];
+        self.hints[i].get_or_insert_with(|| hint::plain(exercise))
+    }
+
+    // Reveal the next level of the selected exercise's hint, keeping it in
+    // the progress file as `kiga hint` does
+    fn reveal_hint(&mut self) {
+        let Some(exercise) = self.current() else {
+            return;
+        };
+        let selected = self.selected();
+        let count = self.hint(selected).len();
+        self.hints_seen[selected] = progress::reveal_hint(&exercise.name, count);
+    }
+
+    // The selected exercise, None when nothing is watched
+    fn current(&self) -> Option<&'a Exercise> {
+        self.exercises.get(self.selected()).copied()
+    }
+
+    fn move_selection(&mut self, delta: isize) {
+        let last = self.exercises.len().saturating_sub(1);
+        let selected = self.selected().saturating_add_signed(delta).min(last);
+        self.list.select(Some(selected));
+        self.scroll = 0;
+    }
+
+    fn first_pending(&self) -> Option<usize> {
+        self.outcomes.iter().position(|o| *o != Outcome::Passed)
+    }
+
+    fn draw(&mut self, frame: &mut Frame) {
+        let [main, footer] =
+            Layout::vertical([Constraint::Min(1), Constraint::Length(1)]).areas(frame.area());
+        let [left, right] =
+            Layout::horizontal([Constraint::Percentage(30), Constraint::Percentage(70)])
+                .areas(main);
+
+        let items: Vec<ListItem> = self
+            .exercises
+            .iter()
+            .zip(&self.outcomes)
+            .map(|(exercise, outcome)| {
+                let (mark, color) = match outcome {
+                    Outcome::Passed => ("✓", Color::Green),
+                    Outcome::Pending => ("•", Color::Yellow),
+                    Outcome::Failed => ("✗", Color::Red),
+                };
+                ListItem::new(Line::from(vec![
+                    Span::styled(mark, paint(color)),
+                    Span::raw(" "),
+                    Span::raw(exercise.name.as_str()),
+                ]))
+            })
+            .collect();
+        let done = self
+            .outcomes
+            .iter()
+            .filter(|o| **o == Outcome::Passed)
+            .count();
+        let title = format!(" Exercises {done}/{} ", self.exercises.len());
+        // The progress of each category, when the catalogue has categories
+        let entries: Vec<(&Exercise, Outcome)> = self
+            .exercises
+            .iter()
+            .copied()
+            .zip(self.outcomes.iter().copied())
+            .collect();
+        let mut categories: Vec<String> = Vec::new();
+        if !self.categories.is_empty() {
+            for (category, members) in category::group(&entries, |(e, _)| &e.name, self.categories)
+            {
+                let done = members
+                    .iter()
+                    .filter(|(_, o)| *o == Outcome::Passed)
+                    .count();
+                categories.push(format!(" {category} {done}/{} ", members.len()));
+            }
+        }
+        let list = List::new(items)
+            .block(
+                Block::bordered()
+                    .title(title)
+                    .title_bottom(categories.join("·")),
+            )
+            .highlight_style(Style::default().add_modifier(Modifier::REVERSED));
+        frame.render_stateful_widget(list, left, &mut self.list);
+
+        let (output_area, hint_area) = if self.show_hint {
+            let [output, hint] =
+                Layout::vertical([Constraint::Percentage(70), Constraint::Percentage(30)])
+                    .areas(right);
+            (output, Some(hint))
+        } else {
+            (right, None)
+        };
+
+        if let Some(exercise) = self.current() {
+            let output = self.outputs[self.selected()]
+                .as_deref()
+                .unwrap_or("Not checked yet, press r to run it");
+            let output = Paragraph::new(output)
+                .block(Block::bordered().title(format!(" Output: {exercise} ")))
+                .wrap(Wrap { trim: false })
+                .scroll((self.scroll, 0));
+            frame.render_widget(output, output_area);
+
+            if let Some(area) = hint_area {
+                let selected = self.selected();
+                let shown = self.hints_seen[selected];
+                let hint = Paragraph::new(hint::levels(self.hint(selected), shown))
+                    .block(Block::bordered().title(format!(" Hint: {exercise} ")))
+                    .wrap(Wrap { trim: false });
+                frame.render_widget(hint, area);
+            }
+        }
+
+        let footer_line = Line::from(vec![
+            Span::styled(
+                self.status.as_str(),
+                Style::default().add_modifier(Modifier::BOLD),
+            ),
+            Span::raw("  "),
+            Span::styled(KEYS, paint(Color::DarkGray)),
+        ]);
+        frame.render_widget(Paragraph::new(footer_line), footer);
+    }
+}
+
+// A foreground color, unless colors are turned off
+fn paint(color: Color) -> Style {
+    if config::get().colors() != Some(false) {
+        Style::default().fg(color)
+    } else {
+        Style::default()
+    }
+}
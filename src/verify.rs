@@ -1,18 +1,26 @@
-use crate::exercise::{CompiledExercise, Exercise, Mode, State};
+use crate::config;
+use crate::criteria::Criterion;
+use crate::exercise::{Exercise, ExerciseOutput, Mode, State};
+use crate::hint;
+use crate::hooks;
+use crate::job;
+use crate::progress::{self, Origin, Outcome};
 use console::style;
 use indicatif::{ProgressBar, ProgressStyle};
-use std::{env, time::Duration};
+use std::time::{Duration, Instant};
 
 // Verify that the provided container of Exercise objects
 // can be compiled and run without any failures.
 // Any such failures will be reported to the end user.
 // If the Exercise being verified is a test, the verbose boolean
 // determines whether or not the test harness outputs are displayed.
+// Every attempt is recorded in the progress database under the given origin.
 pub fn verify<'a>(
     exercises: impl IntoIterator<Item = &'a Exercise>,
     progress: (usize, usize),
     verbose: bool,
     success_hints: bool,
+    origin: Origin,
 ) -> Result<(), &'a Exercise> {
     let (num_done, total) = progress;
     let bar = ProgressBar::new(total as u64);
@@ -27,12 +35,22 @@ pub fn verify<'a>(
     bar.set_message(format!("({:.1} %)", percentage));
 
     for exercise in exercises {
-        let compile_result = match exercise.mode {
-            Mode::Test => compile_and_test(exercise, RunMode::Interactive, verbose, success_hints),
-            Mode::Compile => compile_and_run_interactively(exercise, success_hints),
-            Mode::Clippy => compile_only(exercise, success_hints),
-        };
-        if !compile_result.unwrap_or(false) {
+        let started = Instant::now();
+        let progress_bar = spinner();
+        let finding = examine(exercise, &progress_bar, true);
+        progress_bar.finish_and_clear();
+        // A newer change in watch mode stopped the check, so it isn't an attempt
+        if job::cancelled() {
+            return Err(exercise);
+        }
+        let summary = summarize(exercise, finding, verbose, success_hints);
+        let outcome = summary.outcome;
+        if outcome != Outcome::Passed || verbose {
+            summary.print();
+        }
+        progress::record(&exercise.name, origin, outcome, started.elapsed());
+        hooks::finished(exercise, outcome);
+        if outcome != Outcome::Passed {
             return Err(exercise);
         }
         percentage += 100.0 / total as f32;
@@ -42,134 +60,154 @@ pub fn verify<'a>(
     Ok(())
 }
 
-enum RunMode {
-    Interactive,
-    NonInteractive,
-}
-
 // Compile and run the resulting test harness of the given Exercise
 pub fn test(exercise: &Exercise, verbose: bool) -> Result<(), ()> {
-    compile_and_test(exercise, RunMode::NonInteractive, verbose, false)?;
-    Ok(())
+    let bar = spinner();
+    let finding = examine(exercise, &bar, true);
+    bar.finish_and_clear();
+    match finding {
+        Finding::Built(output) => {
+            if verbose {
+                println!("{}", output.unwrap_or_default());
+            }
+            Ok(())
+        }
+        finding => {
+            summarize(exercise, finding, verbose, false).print();
+            Err(())
+        }
+    }
 }
 
-// Invoke the rust compiler without running the resulting binary
-fn compile_only(exercise: &Exercise, success_hints: bool) -> Result<bool, ()> {
-    let progress_bar = ProgressBar::new_spinner();
-    progress_bar.set_message(format!("Compiling {exercise}..."));
-    progress_bar.enable_steady_tick(Duration::from_millis(100));
-
-    let _ = compile(exercise, &progress_bar)?;
-    progress_bar.finish_and_clear();
-
-    Ok(prompt_for_completion(exercise, None, success_hints))
+// What building, and for most modes running, an exercise came to
+enum Finding {
+    CompileFailed(ExerciseOutput),
+    RunFailed(ExerciseOutput),
+    // The standard output of the run, None if the exercise is only compiled
+    Built(Option<String>),
 }
 
-// Compile the given Exercise and run the resulting binary in an interactive mode
-fn compile_and_run_interactively(exercise: &Exercise, success_hints: bool) -> Result<bool, ()> {
-    let progress_bar = ProgressBar::new_spinner();
-    progress_bar.set_message(format!("Compiling {exercise}..."));
-    progress_bar.enable_steady_tick(Duration::from_millis(100));
-
-    let compilation = compile(exercise, &progress_bar)?;
-
-    progress_bar.set_message(format!("Running {exercise}..."));
-    let result = compilation.run();
-    progress_bar.finish_and_clear();
-
-    let output = match result {
-        Ok(output) => output,
-        Err(output) => {
-            warn!("Ran {} with errors", exercise);
-            println!("{}", output.stdout);
-            println!("{}", output.stderr);
-            return Err(());
-        }
-    };
-
-    Ok(prompt_for_completion(
-        exercise,
-        Some(output.stdout),
-        success_hints,
-    ))
+fn spinner() -> ProgressBar {
+    let bar = ProgressBar::new_spinner();
+    bar.enable_steady_tick(Duration::from_millis(100));
+    bar
 }
 
-// Compile the given Exercise as a test harness and display
-// the output if verbose is set to true
-fn compile_and_test(
-    exercise: &Exercise,
-    run_mode: RunMode,
-    verbose: bool,
-    success_hints: bool,
-) -> Result<bool, ()> {
-    let progress_bar = ProgressBar::new_spinner();
-    progress_bar.set_message(format!("Testing {exercise}..."));
-    progress_bar.enable_steady_tick(Duration::from_millis(100));
-
-    let compilation = compile(exercise, &progress_bar)?;
-    let result = compilation.run();
-    progress_bar.finish_and_clear();
-
-    match result {
-        Ok(output) => {
-            if verbose {
-                println!("{}", output.stdout);
-            }
-            if let RunMode::Interactive = run_mode {
-                Ok(prompt_for_completion(exercise, None, success_hints))
-            } else {
-                Ok(true)
-            }
+// Build the given Exercise and run it unless it's a Clippy exercise,
+// telling the progress bar what's going on. With `attempt` set, the
+// compile and run hooks fire around the steps
+fn examine(exercise: &Exercise, progress_bar: &ProgressBar, attempt: bool) -> Finding {
+    match exercise.mode {
+        Mode::Test => progress_bar.set_message(format!("Testing {exercise}...")),
+        Mode::Compile | Mode::Clippy => {
+            progress_bar.set_message(format!("Compiling {exercise}..."))
         }
+    }
+    let compiled = if attempt {
+        hooks::compile(exercise)
+    } else {
+        exercise.compile()
+    };
+    let compilation = match compiled {
+        Ok(compilation) => compilation,
         Err(output) => {
-            warn!(
-                "Testing of {} failed! Please try again. Here's the output:",
-                exercise
-            );
-            println!("{}", output.stdout);
-            Err(())
+            exercise.set_build_errors(Some(output.stderr.clone()));
+            return Finding::CompileFailed(output);
         }
+    };
+    exercise.set_build_errors(None);
+    if exercise.mode == Mode::Clippy {
+        return Finding::Built(None);
+    }
+    if exercise.mode == Mode::Compile {
+        progress_bar.set_message(format!("Running {exercise}..."));
+    }
+    let output = if attempt {
+        hooks::run_compiled(exercise, &compilation)
+    } else {
+        compilation.run()
+    };
+    match output {
+        Ok(output) => Finding::Built(Some(output.stdout)),
+        Err(output) => Finding::RunFailed(output),
     }
 }
 
-// Compile the given Exercise and return an object with information
-// about the state of the compilation
-fn compile<'a>(
-    exercise: &'a Exercise,
-    progress_bar: &ProgressBar,
-) -> Result<CompiledExercise<'a>, ()> {
-    let compilation_result = exercise.compile();
+// What a check found, as it's shown to the user: a headline, in green
+// unless the exercise failed, and the details below it
+struct Summary {
+    outcome: Outcome,
+    headline: String,
+    details: String,
+}
 
-    match compilation_result {
-        Ok(compilation) => Ok(compilation),
-        Err(output) => {
-            progress_bar.finish_and_clear();
-            warn!(
-                "Compiling of {} failed! Please try again. Here's the output:",
-                exercise
-            );
-            println!("{}", output.stderr);
-            Err(())
+impl Summary {
+    fn print(&self) {
+        if self.outcome == Outcome::Failed {
+            warn!("{}", self.headline);
+        } else {
+            success!("{}", self.headline);
+        }
+        if !self.details.is_empty() {
+            println!("{}", self.details.trim_end());
         }
     }
 }
 
-fn prompt_for_completion(
-    exercise: &Exercise,
-    prompt_output: Option<String>,
-    success_hints: bool,
-) -> bool {
-    let context = match exercise.state() {
-        State::Done => return true,
-        State::Pending(context) => context,
+// Describe a finding, checking the exercise's completion criteria if it
+// built. The output of a run is shown for exercises that print something
+// for the user, and for tests only if `verbose` is set
+fn summarize(exercise: &Exercise, finding: Finding, verbose: bool, success_hints: bool) -> Summary {
+    let failure = |headline: String, details: String| Summary {
+        outcome: Outcome::Failed,
+        headline,
+        details,
+    };
+    let run_output = match finding {
+        Finding::CompileFailed(output) => {
+            return failure(
+                format!("Compiling of {exercise} failed! Please try again. Here's the output:"),
+                output.stderr,
+            )
+        }
+        Finding::RunFailed(output) if exercise.mode == Mode::Test => {
+            return failure(
+                format!("Testing of {exercise} failed! Please try again. Here's the output:"),
+                output.stdout,
+            )
+        }
+        Finding::RunFailed(output) => {
+            return failure(
+                format!("Ran {exercise} with errors"),
+                format!("{}\n{}", output.stdout, output.stderr),
+            )
+        }
+        Finding::Built(output) => output,
+    };
+    let headline = match exercise.mode {
+        Mode::Compile => format!("Successfully ran {exercise}!"),
+        Mode::Test => format!("Successfully tested {exercise}!"),
+        Mode::Clippy => format!("Successfully compiled {exercise}!"),
+    };
+    let shown_output = run_output
+        .as_deref()
+        .filter(|_| verbose || exercise.mode == Mode::Compile);
+    let mut details = String::new();
+    let unmet = match exercise.state_after_build(run_output.as_deref()) {
+        State::Done => {
+            if let Some(output) = shown_output {
+                push_section(&mut details, "Output", output);
+            }
+            return Summary {
+                outcome: Outcome::Passed,
+                headline,
+                details,
+            };
+        }
+        State::Pending(unmet) => unmet,
     };
-    match exercise.mode {
-        Mode::Compile => success!("Successfully ran {}!", exercise),
-        Mode::Test => success!("Successfully tested {}!", exercise),
-        Mode::Clippy => success!("Successfully compiled {}!", exercise),
-    }
 
-    let no_emoji = env::var("NO_EMOJI").is_ok();
+    let no_emoji = !config::get().emoji();
 
     let clippy_success_msg = if no_emoji {
         "The code is compiling, and Clippy is happy!"
@@ -182,53 +220,160 @@ fn prompt_for_completion(
         Mode::Test => "The code is compiling, and the tests pass!",
         Mode::Clippy => clippy_success_msg,
     };
-    println!();
+    details.push('\n');
     if no_emoji {
-        println!("~*~ {success_msg} ~*~")
+        details.push_str(&format!("~*~ {success_msg} ~*~\n\n"));
     } else {
-        println!("🎉 🎉  {success_msg} 🎉 🎉")
+        details.push_str(&format!("🎉 🎉  {success_msg} 🎉 🎉\n\n"));
     }
-    println!();
 
-    if let Some(output) = prompt_output {
-        println!("Output:");
-        println!("{}", separator());
-        println!("{output}");
-        println!("{}", separator());
-        println!();
+    if let Some(output) = shown_output {
+        push_section(&mut details, "Output", output);
     }
     if success_hints {
-        println!("Hints:");
-        println!("{}", separator());
-        println!("{}", exercise.hint);
-        println!("{}", separator());
-        println!();
+        push_section(&mut details, "Hints", &hint::all(exercise));
     }
 
-    println!("You can keep working on this exercise,");
-    println!(
-        "or jump into the next one by removing the {} comment:",
-        style("`I AM NOT DONE`").bold()
-    );
-    println!();
-    for context_line in context {
-        let formatted_line = if context_line.important {
-            format!("{}", style(context_line.line).bold())
-        } else {
-            context_line.line.to_string()
-        };
+    details.push_str("You can keep working on this exercise,\n");
+    if unmet.criterion != Criterion::MarkerAbsent {
+        details.push_str(&format!(
+            "it will be done once {}.",
+            style(&unmet.criterion).bold()
+        ));
+    } else {
+        details.push_str(&format!(
+            "or jump into the next one by removing the {} comment:\n",
+            style("`I AM NOT DONE`").bold()
+        ));
+        for context_line in unmet.context {
+            let formatted_line = if context_line.important {
+                format!("{}", style(context_line.line).bold())
+            } else {
+                context_line.line.to_string()
+            };
 
-        println!(
-            "{:>2} {}  {}",
-            style(context_line.number).blue().bold(),
-            style("|").blue(),
-            formatted_line
+            details.push_str(&format!(
+                "\n{:>2} {}  {}",
+                style(context_line.number).blue().bold(),
+                style("|").blue(),
+                formatted_line
+            ));
+        }
+    }
+    Summary {
+        outcome: Outcome::Pending,
+        headline,
+        details,
+    }
+}
+
+// A titled block of text between separators
+fn push_section(details: &mut String, title: &str, text: &str) {
+    details.push_str(&format!(
+        "{title}:\n{separator}\n{text}\n{separator}\n\n",
+        separator = separator()
+    ));
+}
+
+// Build and run an exercise without printing anything, for the watch mode
+// TUI and the focused watch modes. Returns the outcome and the text worth
+// showing, without colors. The attempt is recorded like any other watch
+// mode attempt
+pub fn check(exercise: &Exercise, verbose: bool) -> (Outcome, String) {
+    let started = Instant::now();
+    let summary = check_quietly(exercise, verbose, true);
+    if !job::cancelled() {
+        progress::record(
+            &exercise.name,
+            Origin::Watch,
+            summary.outcome,
+            started.elapsed(),
         );
+        hooks::finished(exercise, summary.outcome);
     }
+    let text = format!("{}\n\n{}", summary.headline, summary.details.trim_end());
+    (
+        summary.outcome,
+        console::strip_ansi_codes(&text).into_owned(),
+    )
+}
 
-    false
+// Build and run the stored solution of every exercise that has one, so
+// the catalogue can't drift away from its answers. Solutions aren't
+// attempts, so nothing is recorded. Returns the number of failures
+pub fn verify_solutions<'a>(exercises: impl IntoIterator<Item = &'a Exercise>) -> usize {
+    let mut checked = 0;
+    let mut failed = 0;
+    for exercise in exercises {
+        let Some(solution) = &exercise.solution else {
+            continue;
+        };
+        checked += 1;
+        let bar = spinner();
+        bar.set_message(format!("Checking the solution of {}...", exercise.name));
+        // Solutions aren't attempts at the exercise, so no hooks fire for them
+        let summary = check_quietly(&exercise.at(solution), false, false);
+        bar.finish_and_clear();
+        if summary.outcome == Outcome::Passed {
+            success!("The solution of {} passes", exercise.name);
+        } else {
+            failed += 1;
+            warn!("The solution of {} doesn't pass:", exercise.name);
+            summary.print();
+        }
+    }
+    if checked == 0 {
+        println!("None of these exercises has a solution.");
+    } else {
+        println!("{} of {checked} solutions pass.", checked - failed);
+    }
+    failed
+}
+
+fn check_quietly(exercise: &Exercise, verbose: bool, attempt: bool) -> Summary {
+    let finding = examine(exercise, &ProgressBar::hidden(), attempt);
+    summarize(exercise, finding, verbose, false)
 }
 
 fn separator() -> console::StyledObject<&'static str> {
     style("====================").bold()
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::path::PathBuf;
+
+    #[test]
+    fn test_failing_solutions_are_counted() {
+        let mut passing = Exercise::named("passing");
+        passing.solution = Some(PathBuf::from("tests/fixture/state/finished_exercise.rs"));
+        let mut failing = Exercise::named("failing");
+        failing.solution = Some(PathBuf::from("tests/fixture/state/pending_exercise.rs"));
+        let unsolved = Exercise::named("unsolved");
+
+        assert_eq!(verify_solutions([&passing, &failing, &unsolved]), 1);
+        assert_eq!(verify_solutions([&unsolved]), 0);
+    }
+
+    #[test]
+    fn test_build_errors_are_kept_for_explain() {
+        let dir = std::env::temp_dir().join(format!("kiga_build_errors_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let mut exercise = Exercise::named("build_errors");
+        exercise.path = dir.join("exercise.rs");
+        std::fs::write(
+            &exercise.path,
+            "fn main() {\n    let x: u32 = \"one\";\n}\n",
+        )
+        .unwrap();
+        check_quietly(&exercise, false, false);
+        let stderr = exercise.build_errors().unwrap();
+        assert_eq!(crate::shell::error_codes(&stderr), ["E0308"]);
+
+        std::fs::write(&exercise.path, "fn main() {}\n").unwrap();
+        check_quietly(&exercise, false, false);
+        assert_eq!(exercise.build_errors(), None);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,327 @@
+use crate::config;
+use glob::Pattern;
+use ignore::gitignore::{Gitignore, GitignoreBuilder};
+use notify::event::{AccessKind, AccessMode};
+use notify::{EventKind, PollWatcher, RecommendedWatcher, RecursiveMode};
+use notify_debouncer_full::{
+    new_debouncer, new_debouncer_opt, DebounceEventResult, Debouncer, RecommendedCache,
+};
+use std::collections::BTreeSet;
+use std::ffi::OsStr;
+use std::path::{Component, Path, PathBuf};
+use std::sync::mpsc::{channel, Receiver, RecvTimeoutError};
+use std::time::Duration;
+
+// Project specific ignore rules, in the .gitignore format
+pub const IGNORE_FILE: &str = ".kigaignore";
+// The directory holding the exercises, watched recursively
+const EXERCISES_DIR: &str = "exercises";
+// How often to poll when falling back from file system events
+const DEFAULT_POLL_MS: u64 = 1000;
+
+// A change watch mode reacts to
+#[derive(Debug, PartialEq)]
+pub enum Change {
+    // A Rust file was saved, with its canonical path
+    Source(PathBuf),
+    // info.toml or one of the catalogues it includes changed
+    Catalogue,
+}
+
+// Where file changes come from
+enum Backend {
+    // The operating system's file system events, like inotify
+    Native(Debouncer<RecommendedWatcher, RecommendedCache>),
+    // Scanning the watched files for new modification times and contents,
+    // which works where events don't: network filesystems, some container
+    // bind mounts, and when the inotify limit is reached
+    Poll(Debouncer<PollWatcher, RecommendedCache>, Duration),
+}
+
+// Watches the exercises and the catalogue of a project. Events are debounced
+// by `watch.debounce_ms` and files ignored by .gitignore or .kigaignore are
+// left out
+pub struct FileWatcher {
+    backend: Backend,
+    events: Receiver<DebounceEventResult>,
+    // Why file system events couldn't be used, when falling back to polling
+    fallback: Option<notify::Error>,
+    root: PathBuf,
+    ignore: Gitignore,
+    manifest: PathBuf,
+    include: Vec<Pattern>,
+    watched: BTreeSet<PathBuf>,
+}
+
+impl FileWatcher {
+    // Watch the project in `root`, whose catalogue is `manifest` plus the
+    // files matched by the `include` patterns. Polls when `watch.poll_ms` is
+    // set, or when file system events can't be used
+    pub fn new(root: &Path, manifest: &Path, include: &[String]) -> notify::Result<Self> {
+        let settings = config::get();
+        let debounce = Duration::from_millis(settings.watch_debounce_ms());
+        let poll = settings.watch_poll_ms().map(Duration::from_millis);
+        match poll {
+            Some(interval) => Self::with(root, manifest, include, debounce, Some(interval)),
+            None => Self::with(root, manifest, include, debounce, None).or_else(|e| {
+                let interval = Duration::from_millis(DEFAULT_POLL_MS);
+                let mut watcher = Self::with(root, manifest, include, debounce, Some(interval))?;
+                watcher.fallback = Some(e);
+                Ok(watcher)
+            }),
+        }
+    }
+
+    fn with(
+        root: &Path,
+        manifest: &Path,
+        include: &[String],
+        debounce: Duration,
+        poll: Option<Duration>,
+    ) -> notify::Result<Self> {
+        let root = root.canonicalize()?;
+        let (tx, events) = channel();
+        let backend = match poll {
+            None => Backend::Native(new_debouncer(debounce, None, tx)?),
+            Some(interval) => {
+                let config = notify::Config::default()
+                    .with_poll_interval(interval)
+                    .with_compare_contents(true);
+                let debouncer =
+                    new_debouncer_opt(debounce, None, tx, RecommendedCache::new(), config)?;
+                Backend::Poll(debouncer, interval)
+            }
+        };
+        let mut watcher = FileWatcher {
+            backend,
+            events,
+            fallback: None,
+            ignore: ignore_rules(&root),
+            manifest: root.join(manifest),
+            include: Vec::new(),
+            watched: BTreeSet::new(),
+            root,
+        };
+        watcher.watch(Path::new(EXERCISES_DIR), RecursiveMode::Recursive)?;
+        // Editors that save atomically replace info.toml by renaming another
+        // file over it, which only the watch on its directory sees
+        let manifest_dir = watcher
+            .manifest
+            .parent()
+            .unwrap_or(&watcher.root)
+            .to_path_buf();
+        watcher.watch(&manifest_dir, RecursiveMode::NonRecursive)?;
+        watcher.set_include(include)?;
+        Ok(watcher)
+    }
+
+    // Follow the included catalogues of a reloaded info.toml
+    pub fn set_include(&mut self, include: &[String]) -> notify::Result<()> {
+        self.include = include
+            .iter()
+            .filter_map(|pattern| Pattern::new(pattern).ok())
+            .collect();
+        for pattern in include {
+            let dir = literal_prefix(pattern);
+            if self.root.join(&dir).is_dir() {
+                self.watch(&dir, RecursiveMode::Recursive)?;
+            }
+        }
+        Ok(())
+    }
+
+    pub fn manifest(&self) -> &Path {
+        &self.manifest
+    }
+
+    // A note for the user when the watcher polls, saying why
+    pub fn polling_note(&self) -> Option<String> {
+        let Backend::Poll(_, interval) = &self.backend else {
+            return None;
+        };
+        let note = format!("Polling for changes every {}ms", interval.as_millis());
+        Some(match &self.fallback {
+            Some(e) => format!("{note}, file system events are unavailable: {e}"),
+            None => note,
+        })
+    }
+
+    // Wait up to `timeout` for changes, returning every change seen in the
+    // meantime once, in order
+    pub fn changes(&self, timeout: Duration) -> notify::Result<Vec<Change>> {
+        let mut changes = Vec::new();
+        let mut next = self.events.recv_timeout(timeout);
+        loop {
+            let events = match next {
+                Ok(Ok(events)) => events,
+                Ok(Err(mut errors)) => return Err(errors.remove(0)),
+                Err(RecvTimeoutError::Timeout) => break,
+                Err(RecvTimeoutError::Disconnected) => {
+                    return Err(notify::Error::generic("the file watcher stopped"))
+                }
+            };
+            // Opening or reading a file, which checking an exercise does,
+            // isn't a change, but closing it after writing is
+            let paths = events
+                .iter()
+                .filter(|e| match e.kind {
+                    EventKind::Access(kind) => kind == AccessKind::Close(AccessMode::Write),
+                    _ => true,
+                })
+                .flat_map(|e| &e.paths);
+            for change in paths.filter_map(|path| self.classify(path)) {
+                if !changes.contains(&change) {
+                    changes.push(change);
+                }
+            }
+            next = self.events.recv_timeout(Duration::ZERO);
+        }
+        Ok(changes)
+    }
+
+    fn watch(&mut self, dir: &Path, mode: RecursiveMode) -> notify::Result<()> {
+        let dir = self.root.join(dir);
+        if self.watched.insert(dir.clone()) {
+            match &mut self.backend {
+                Backend::Native(debouncer) => debouncer.watch(&dir, mode)?,
+                Backend::Poll(debouncer, _) => debouncer.watch(&dir, mode)?,
+            }
+        }
+        Ok(())
+    }
+
+    // What a changed path means to watch mode, if anything. Removed files
+    // are skipped, and so are files matched by the ignore rules
+    fn classify(&self, path: &Path) -> Option<Change> {
+        let path = path.canonicalize().ok()?;
+        let relative = path.strip_prefix(&self.root).ok()?;
+        if path == self.manifest || self.include.iter().any(|p| p.matches_path(relative)) {
+            return Some(Change::Catalogue);
+        }
+        if path.extension() != Some(OsStr::new("rs")) || !relative.starts_with(EXERCISES_DIR) {
+            return None;
+        }
+        if self
+            .ignore
+            .matched_path_or_any_parents(relative, false)
+            .is_ignore()
+        {
+            return None;
+        }
+        Some(Change::Source(path))
+    }
+}
+
+// The rules of the project's .gitignore and .kigaignore. A missing or
+// unreadable file contributes no rules
+fn ignore_rules(root: &Path) -> Gitignore {
+    let mut builder = GitignoreBuilder::new(root);
+    for file in [".gitignore", IGNORE_FILE] {
+        let _ = builder.add(root.join(file));
+    }
+    builder.build().unwrap_or_else(|_| Gitignore::empty())
+}
+
+// The directory part of a glob pattern before its first wildcard,
+// e.g. `catalogues` for `catalogues/*.toml`
+fn literal_prefix(pattern: &str) -> PathBuf {
+    let path = Path::new(pattern);
+    let literal: PathBuf = path
+        .components()
+        .take_while(|c| match c {
+            Component::Normal(part) => !part.to_string_lossy().contains(['*', '?', '[', '{']),
+            _ => true,
+        })
+        .collect();
+    if literal == path {
+        literal.parent().map(Path::to_path_buf).unwrap_or_default()
+    } else {
+        literal
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::fs;
+    use std::thread;
+    use std::time::Instant;
+
+    #[test]
+    fn test_classify_ignores_and_catalogues() {
+        let root = std::env::temp_dir().join(format!("kiga-watcher-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(root.join("exercises/scratch")).unwrap();
+        fs::create_dir_all(root.join("catalogues")).unwrap();
+        fs::write(root.join(IGNORE_FILE), "exercises/scratch/\n*.bak.rs\n").unwrap();
+        for file in [
+            "info.toml",
+            "catalogues/extra.toml",
+            "exercises/intro.rs",
+            "exercises/intro.bak.rs",
+            "exercises/scratch/try.rs",
+            "exercises/notes.txt",
+        ] {
+            fs::write(root.join(file), "").unwrap();
+        }
+
+        let include = ["catalogues/*.toml".to_string()];
+        let watcher = FileWatcher::with(
+            &root,
+            Path::new("info.toml"),
+            &include,
+            Duration::from_millis(10),
+            None,
+        )
+        .unwrap();
+        let classify = |file: &str| watcher.classify(&root.join(file));
+
+        assert_eq!(classify("info.toml"), Some(Change::Catalogue));
+        assert_eq!(classify("catalogues/extra.toml"), Some(Change::Catalogue));
+        let intro = root.join("exercises/intro.rs").canonicalize().unwrap();
+        assert_eq!(classify("exercises/intro.rs"), Some(Change::Source(intro)));
+        assert_eq!(classify("exercises/intro.bak.rs"), None);
+        assert_eq!(classify("exercises/scratch/try.rs"), None);
+        assert_eq!(classify("exercises/notes.txt"), None);
+        assert_eq!(classify("exercises/removed.rs"), None);
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn test_polling_sees_new_contents() {
+        let root = std::env::temp_dir().join(format!("kiga-poll-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(root.join("exercises")).unwrap();
+        fs::write(root.join("info.toml"), "").unwrap();
+        fs::write(root.join("exercises/intro.rs"), "fn main() {}").unwrap();
+
+        let watcher = FileWatcher::with(
+            &root,
+            Path::new("info.toml"),
+            &[],
+            Duration::from_millis(20),
+            Some(Duration::from_millis(20)),
+        )
+        .unwrap();
+        assert!(watcher.polling_note().is_some());
+        // Let the first scan record the current state
+        thread::sleep(Duration::from_millis(100));
+        fs::write(root.join("exercises/intro.rs"), "fn main() { todo!() }").unwrap();
+
+        let intro = root.join("exercises/intro.rs").canonicalize().unwrap();
+        let deadline = Instant::now() + Duration::from_secs(5);
+        let mut changes = Vec::new();
+        while changes.is_empty() && Instant::now() < deadline {
+            changes = watcher.changes(Duration::from_millis(100)).unwrap();
+        }
+        assert_eq!(changes, [Change::Source(intro)]);
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn test_literal_prefix() {
+        assert_eq!(literal_prefix("catalogues/*.toml"), Path::new("catalogues"));
+        assert_eq!(literal_prefix("a/b/**/c.toml"), Path::new("a/b"));
+        assert_eq!(literal_prefix("extra.toml"), Path::new(""));
+    }
+}